@@ -7,14 +7,33 @@ use rusqlite::{params, Connection};
 use serde::Deserialize;
 use tokio::sync::Mutex;
 
+use crate::extractors::ps_addons::extract_title::DEFAULT_TITLE_SUFFIXES;
+
+/// Delay in hours before the first retry of a failed scrape, doubled on each failure.
+const DEFAULT_RETRY_BACKOFF: u32 = 1;
+
+/// Duration in seconds of the lease reserving a URL to the worker processing it.
+const DEFAULT_LEASE_DURATION: u32 = 600;
+
+/// Number of consecutive sitemap refreshes a URL must be missing from before its removal is checked.
+const DEFAULT_REMOVAL_THRESHOLD: u32 = 3;
+
 #[derive(Deserialize)]
 struct Settings {
     base: Base,
     processing: Processing,
     prestashop_addon: PrestashopAddon,
     flaresolverr: Flaresolverr,
+    #[serde(default)]
+    extractors: Extractors,
     wordpress_api: WordPressApi,
     wordpress_page: WordPressPage,
+    #[serde(default)]
+    woocommerce: WooCommerce,
+    #[serde(default)]
+    content: Content,
+    #[serde(default)]
+    pricing: Pricing,
 }
 
 #[derive(Deserialize)]
@@ -28,13 +47,18 @@ struct Processing {
     batch_size: u32,
     max_concurrency: u32,
     age_url: u32,
+    retry_backoff: Option<u32>,
+    lease_duration: Option<u32>,
 }
 
 #[derive(Deserialize)]
 struct PrestashopAddon {
     robots_url: String,
     sitemap_lang: String,
+    #[serde(default)]
+    sitemap_langs: Vec<String>,
     sitemap_frequency_update: u32,
+    removal_threshold: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -43,6 +67,24 @@ struct Flaresolverr {
     user_agent: String,
 }
 
+#[derive(Deserialize)]
+#[serde(default)]
+struct Extractors {
+    rules_file: String,
+    title_suffixes: Vec<String>,
+    scrape_developers: bool,
+}
+
+impl Default for Extractors {
+    fn default() -> Self {
+        Self {
+            rules_file: String::new(),
+            title_suffixes: DEFAULT_TITLE_SUFFIXES.map(String::from).to_vec(),
+            scrape_developers: false,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct WordPressApi {
     wordpress_url: String,
@@ -58,6 +100,47 @@ struct WordPressPage {
     author: u32,
 }
 
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct WooCommerce {
+    import_reviews: bool,
+    reviewer_email: String,
+    ps_version_attribute: String,
+    link_related_modules: bool,
+    developer_brands: bool,
+    theme_parent: u32,
+    translation_plugin: String,
+    removed_products: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Content {
+    addons_links: String,
+    addons_links_rewrite_url: String,
+    rehost_images: bool,
+    videos: String,
+    videos_meta_key: String,
+    sections: String,
+    sections_meta_prefix: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Pricing {
+    vat_rate: f64,
+    prices_include_tax: bool,
+    markup_percent: f64,
+    markup_fixed: f64,
+    rounding: String,
+    ending: f64,
+    minimum_price: f64,
+    free_modules: String,
+    free_module_price: f64,
+    shop_currency: String,
+    rates_file: String,
+}
+
 pub async fn load_configuration(db: &Arc<Mutex<Connection>>, file_path: &str) -> Result<()> {
     let db = db.lock().await;
 
@@ -91,6 +174,28 @@ pub async fn load_configuration(db: &Arc<Mutex<Connection>>, file_path: &str) ->
         "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
         params!["age_url", settings.processing.age_url.to_string()],
     )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "retry_backoff",
+            settings
+                .processing
+                .retry_backoff
+                .unwrap_or(DEFAULT_RETRY_BACKOFF)
+                .to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "lease_duration",
+            settings
+                .processing
+                .lease_duration
+                .unwrap_or(DEFAULT_LEASE_DURATION)
+                .to_string()
+        ],
+    )?;
     db.execute(
         "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
         params!["robots_url", settings.prestashop_addon.robots_url],
@@ -99,6 +204,17 @@ pub async fn load_configuration(db: &Arc<Mutex<Connection>>, file_path: &str) ->
         "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
         params!["sitemap_lang", settings.prestashop_addon.sitemap_lang],
     )?;
+
+    // Languages of the sitemaps to ingest, the first one is used for the WooCommerce products
+    let sitemap_langs = if settings.prestashop_addon.sitemap_langs.is_empty() {
+        vec![settings.prestashop_addon.sitemap_lang.clone()]
+    } else {
+        settings.prestashop_addon.sitemap_langs.clone()
+    };
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["sitemap_langs", serde_json::to_string(&sitemap_langs)?],
+    )?;
     db.execute(
         "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
         params![
@@ -109,6 +225,17 @@ pub async fn load_configuration(db: &Arc<Mutex<Connection>>, file_path: &str) ->
                 .to_string()
         ],
     )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "removal_threshold",
+            settings
+                .prestashop_addon
+                .removal_threshold
+                .unwrap_or(DEFAULT_REMOVAL_THRESHOLD)
+                .to_string()
+        ],
+    )?;
     db.execute(
         "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
         params!["flaresolverr_url", settings.flaresolverr.flaresolverr_url],
@@ -117,6 +244,24 @@ pub async fn load_configuration(db: &Arc<Mutex<Connection>>, file_path: &str) ->
         "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
         params!["user_agent", settings.flaresolverr.user_agent],
     )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["extractor_rules", settings.extractors.rules_file],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "title_suffixes",
+            serde_json::to_string(&settings.extractors.title_suffixes)?
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "scrape_developers",
+            settings.extractors.scrape_developers.to_string()
+        ],
+    )?;
     db.execute(
         "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
         params!["wordpress_url", settings.wordpress_api.wordpress_url],
@@ -151,6 +296,143 @@ pub async fn load_configuration(db: &Arc<Mutex<Connection>>, file_path: &str) ->
             settings.wordpress_page.author.to_string()
         ],
     )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "import_reviews",
+            settings.woocommerce.import_reviews.to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["reviewer_email", settings.woocommerce.reviewer_email],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "ps_version_attribute",
+            settings.woocommerce.ps_version_attribute
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "link_related_modules",
+            settings.woocommerce.link_related_modules.to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "developer_brands",
+            settings.woocommerce.developer_brands.to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "theme_parent",
+            settings.woocommerce.theme_parent.to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "translation_plugin",
+            settings.woocommerce.translation_plugin
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["removed_products", settings.woocommerce.removed_products],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["addons_links", settings.content.addons_links],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "addons_links_rewrite_url",
+            settings.content.addons_links_rewrite_url
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["rehost_images", settings.content.rehost_images.to_string()],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["videos", settings.content.videos],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["videos_meta_key", settings.content.videos_meta_key],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["sections", settings.content.sections],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "sections_meta_prefix",
+            settings.content.sections_meta_prefix
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["vat_rate", settings.pricing.vat_rate.to_string()],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "prices_include_tax",
+            settings.pricing.prices_include_tax.to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "markup_percent",
+            settings.pricing.markup_percent.to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["markup_fixed", settings.pricing.markup_fixed.to_string()],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["price_rounding", settings.pricing.rounding],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["price_ending", settings.pricing.ending.to_string()],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["minimum_price", settings.pricing.minimum_price.to_string()],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["free_modules", settings.pricing.free_modules],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params![
+            "free_module_price",
+            settings.pricing.free_module_price.to_string()
+        ],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["shop_currency", settings.pricing.shop_currency],
+    )?;
+    db.execute(
+        "INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)",
+        params!["rates_file", settings.pricing.rates_file],
+    )?;
 
     println!(
         "{}",
@@ -159,3 +441,87 @@ pub async fn load_configuration(db: &Arc<Mutex<Connection>>, file_path: &str) ->
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::config::get_configuration::get_configuration_value;
+
+    /// The keys read by the processing of the URLs, directly or through the settings loaders.
+    const PROCESS_KEYS: [&str; 43] = [
+        "addons_links",
+        "addons_links_rewrite_url",
+        "age_url",
+        "batch_size",
+        "developer_brands",
+        "extractor_rules",
+        "flaresolverr_url",
+        "free_module_price",
+        "free_modules",
+        "import_reviews",
+        "lease_duration",
+        "link_related_modules",
+        "markup_fixed",
+        "markup_percent",
+        "max_concurrency",
+        "minimum_price",
+        "password_api",
+        "price_ending",
+        "price_rounding",
+        "prices_include_tax",
+        "ps_version_attribute",
+        "rates_file",
+        "rehost_images",
+        "removal_threshold",
+        "removed_products",
+        "retry_backoff",
+        "reviewer_email",
+        "robots_url",
+        "scrape_developers",
+        "sections",
+        "sections_meta_prefix",
+        "shop_currency",
+        "sitemap_langs",
+        "theme_parent",
+        "title_suffixes",
+        "translation_plugin",
+        "user_agent",
+        "username_api",
+        "vat_rate",
+        "videos",
+        "videos_meta_key",
+        "wordpress_parent",
+        "wordpress_url",
+    ];
+
+    #[tokio::test]
+    async fn stores_every_setting_read_by_the_processing() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE configuration (id INTEGER PRIMARY KEY AUTOINCREMENT,
+            key TEXT NOT NULL UNIQUE, value TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("Settings.toml.example");
+
+        load_configuration(&db, &example.to_string_lossy())
+            .await
+            .unwrap();
+
+        for key in PROCESS_KEYS {
+            assert!(
+                get_configuration_value(&db, key).await.is_ok(),
+                "Missing configuration key: {}",
+                key
+            );
+        }
+        assert_eq!(
+            get_configuration_value(&db, "shop_currency").await.unwrap(),
+            "EUR"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::env;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
@@ -36,3 +37,51 @@ pub async fn get_configuration_value_as_i64(
     ))?;
     Ok(parsed_value)
 }
+
+pub async fn get_configuration_value_as_bool(
+    conn: &Arc<Mutex<Connection>>,
+    key: &str,
+) -> Result<bool> {
+    let value = get_configuration_value(conn, key).await?;
+    let parsed_value = value.parse::<bool>().context(format!(
+        "Failed to parse configuration value as bool for key: {}",
+        key
+    ))?;
+    Ok(parsed_value)
+}
+
+pub async fn get_configuration_value_as_f64(
+    conn: &Arc<Mutex<Connection>>,
+    key: &str,
+) -> Result<f64> {
+    let value = get_configuration_value(conn, key).await?;
+    let parsed_value = value.parse::<f64>().context(format!(
+        "Failed to parse configuration value as f64 for key: {}",
+        key
+    ))?;
+    Ok(parsed_value)
+}
+
+/// Returns the FlareSolverr URL of this worker.
+///
+/// The `FLARESOLVERR_URL` environment variable overrides the configuration, the workers sharing
+/// the database can each use their own FlareSolverr instance.
+pub async fn get_flaresolverr_url(conn: &Arc<Mutex<Connection>>) -> Result<String> {
+    match env::var("FLARESOLVERR_URL") {
+        Ok(url) if !url.is_empty() => Ok(url),
+        _ => get_configuration_value(conn, "flaresolverr_url").await,
+    }
+}
+
+/// Returns the identifier of this worker, from the `WORKER_ID` environment variable or the
+/// host name and process id.
+pub fn get_worker_id() -> String {
+    match env::var("WORKER_ID") {
+        Ok(id) if !id.is_empty() => id,
+        _ => format!(
+            "{}-{}",
+            env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string()),
+            std::process::id()
+        ),
+    }
+}
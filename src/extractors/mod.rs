@@ -1 +1,2 @@
 pub mod ps_addons;
+pub mod rules;
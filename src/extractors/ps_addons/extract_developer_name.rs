@@ -1,19 +1,21 @@
-use scraper::{Html, Selector};
+use scraper::Html;
 
-/// Extracts the developer name from HTML content
-pub fn extract_developer_name(html_content: &str) -> String {
-    let document = Html::parse_document(html_content);
-    let selector = Selector::parse("a[id='ps_link_manufacturer']").unwrap();
+use crate::extractors::ps_addons::extract_images::absolute_url;
+use crate::extractors::rules::ExtractorRules;
 
-    // Select the first link with the specified ID and extract the 'title' attribute
-    document.select(&selector).next().map_or_else(
-        || String::new(), // Return an empty string if no element is found
-        |element| {
-            element
-                .value()
-                .attr("title")
-                .unwrap_or_default()
-                .to_string()
-        },
-    )
+/// Extracts the developer name from HTML content using the `developer_name` rule,
+/// by default the `title` attribute of the manufacturer link.
+pub fn extract_developer_name(document: &Html, rules: &ExtractorRules) -> String {
+    rules
+        .extract("developer_name", document)
+        .unwrap_or_default() // Return an empty string if no element is found
+}
+
+/// Extracts the URL of the developer page using the `developer_url` rule,
+/// by default the `href` attribute of the manufacturer link.
+pub fn extract_developer_url(document: &Html, rules: &ExtractorRules) -> Option<String> {
+    rules
+        .extract("developer_url", document)
+        .map(|url| absolute_url(url.trim()))
+        .filter(|url| !url.is_empty())
 }
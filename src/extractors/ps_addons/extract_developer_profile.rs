@@ -0,0 +1,54 @@
+use scraper::Html;
+
+use serde::Serialize;
+
+use crate::extractors::ps_addons::extract_images::absolute_url;
+use crate::extractors::rules::ExtractorRules;
+
+/// The profile of a developer, read from its Addons page.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct DeveloperProfile {
+    pub name: String,
+    /// Presentation of the developer, as HTML.
+    pub description: Option<String>,
+    /// Absolute URL of the logo.
+    pub logo: Option<String>,
+    pub country: Option<String>,
+    /// Partner badges, e.g. "Partenaire Premium".
+    pub badges: Vec<String>,
+    /// Number of products published by the developer.
+    pub module_count: Option<u32>,
+    pub rating_value: Option<f64>,
+}
+
+/// Extracts the profile of a developer from its Addons page, using the `developer_*` rules.
+///
+/// Returns `None` if the page has no developer name, e.g. if it isn't a developer page.
+pub fn extract_developer_profile(
+    document: &Html,
+    rules: &ExtractorRules,
+) -> Option<DeveloperProfile> {
+    let name = rules
+        .extract("developer_profile_name", document)
+        .filter(|name| !name.is_empty())?;
+
+    Some(DeveloperProfile {
+        name,
+        description: rules
+            .extract("developer_description", document)
+            .filter(|description| !description.is_empty()),
+        logo: rules
+            .extract("developer_logo", document)
+            .map(|logo| absolute_url(logo.trim())),
+        country: rules
+            .extract("developer_country", document)
+            .filter(|country| !country.is_empty()),
+        badges: rules.extract_all("developer_badge", document),
+        module_count: rules
+            .extract("developer_module_count", document)
+            .and_then(|count| count.parse().ok()),
+        rating_value: rules
+            .extract("developer_rating", document)
+            .and_then(|rating| rating.parse().ok()),
+    })
+}
@@ -0,0 +1,203 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use scraper::element_ref::ElementRef;
+use scraper::Html;
+use serde::Serialize;
+
+use crate::extractors::rules::ExtractorRules;
+
+const ADDONS_ORIGIN: &str = "https://addons.prestashop.com";
+
+/// Size suffixes of the variants of a picture, e.g. `-large`, `_thumb` or `-800x600`.
+static SIZE_SUFFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:[-_](?:\d+x\d+|\d+w|small|medium|large|big|thumb|thumbnail|home|cart|zoom|default))+$",
+    )
+    .unwrap()
+});
+
+/// An image of the product gallery.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ProductImage {
+    pub url: String,
+    pub alt: String,
+    pub cover: bool,
+}
+
+/// A gallery image with the width of the selected variant, if known.
+struct Candidate {
+    image: ProductImage,
+    key: String,
+    width: Option<u32>,
+}
+
+/// Extracts the images of the product gallery, in gallery order.
+///
+/// Images are read from the elements matched by the `gallery_image` rule. For each
+/// image the largest variant is kept (`srcset`, `data-srcset`, `data-zoom-image`,
+/// `data-src` then `src`), and variants of the same picture, e.g. the thumbnails,
+/// are merged. The cover is the image matching the `cover_image` rule (by default
+/// the `og:image`), or the first image, and is placed first.
+pub fn extract_images(document: &Html, rules: &ExtractorRules) -> Vec<ProductImage> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for element in rules.select("gallery_image", document.root_element()) {
+        let Some((url, width)) = largest_source(&element) else {
+            continue;
+        };
+        let key = variant_key(&url);
+        let alt = element
+            .value()
+            .attr("alt")
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        // Merge the variants of the same picture, keeping the position of the first one
+        if let Some(existing) = candidates.iter_mut().find(|c| c.key == key) {
+            if width.unwrap_or(0) > existing.width.unwrap_or(0) {
+                existing.image.url = url;
+                existing.width = width;
+            }
+            if existing.image.alt.is_empty() {
+                existing.image.alt = alt;
+            }
+            continue;
+        }
+
+        candidates.push(Candidate {
+            image: ProductImage {
+                url,
+                alt,
+                cover: false,
+            },
+            key,
+            width,
+        });
+    }
+
+    let cover_url = rules
+        .extract("cover_image", document)
+        .map(|url| absolute_url(url.trim()));
+
+    // Use the cover as the only image when the gallery can't be found
+    if candidates.is_empty() {
+        return cover_url
+            .map(|url| {
+                vec![ProductImage {
+                    url,
+                    alt: String::new(),
+                    cover: true,
+                }]
+            })
+            .unwrap_or_default();
+    }
+
+    let cover_index = cover_url
+        .map(|url| variant_key(&url))
+        .and_then(|key| candidates.iter().position(|c| c.key == key))
+        .unwrap_or(0);
+
+    let mut cover = candidates.remove(cover_index);
+    cover.image.cover = true;
+    candidates.insert(0, cover);
+
+    candidates.into_iter().map(|c| c.image).collect()
+}
+
+/// Returns the URL of the largest variant of an image, with its width if known.
+fn largest_source(element: &ElementRef) -> Option<(String, Option<u32>)> {
+    let attr = |name: &str| {
+        element
+            .value()
+            .attr(name)
+            .map(str::trim)
+            .filter(|value| !value.is_empty() && !value.starts_with("data:"))
+    };
+
+    for srcset in [attr("srcset"), attr("data-srcset")].into_iter().flatten() {
+        if let Some((url, width)) = largest_in_srcset(srcset) {
+            return Some((absolute_url(&url), width));
+        }
+    }
+
+    attr("data-zoom-image")
+        .or_else(|| attr("data-src"))
+        .or_else(|| attr("src"))
+        .map(|url| (absolute_url(url), None))
+}
+
+/// Parses a `srcset` such as `a.jpg 320w, b.jpg 1280w` and returns the largest candidate.
+///
+/// Pixel density descriptors (`2x`) are compared as is, the width is only returned for `w` descriptors.
+fn largest_in_srcset(srcset: &str) -> Option<(String, Option<u32>)> {
+    srcset
+        .split(',')
+        .filter_map(|candidate| {
+            let mut parts = candidate.split_whitespace();
+            let url = parts.next()?.to_string();
+            let descriptor = parts.next().unwrap_or("1x");
+
+            let (size, width) = if let Some(w) = descriptor.strip_suffix('w') {
+                let w = w.parse::<u32>().ok()?;
+                (w as f64, Some(w))
+            } else {
+                (descriptor.trim_end_matches('x').parse::<f64>().ok()?, None)
+            };
+
+            Some((url, size, width))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(url, _, width)| (url, width))
+}
+
+/// Resolves a protocol-relative or root-relative URL against the Addons site.
+pub fn absolute_url(url: &str) -> String {
+    if let Some(path) = url.strip_prefix("//") {
+        format!("https://{}", path)
+    } else if url.starts_with('/') {
+        format!("{}{}", ADDONS_ORIGIN, url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Identifies a picture regardless of its variant: directory and file name without
+/// extension, query string and size suffixes such as `-large`, `_thumb` or `-800x600`.
+fn variant_key(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let (directory, file_name) = path.rsplit_once('/').unwrap_or(("", path));
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _)| stem);
+
+    format!("{}/{}", directory, SIZE_SUFFIX.replace(stem, "")).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_only_the_variants_of_the_same_picture() {
+        let rules = ExtractorRules::from_toml("[gallery_image]\nselector = \"img\"").unwrap();
+        let document = Html::parse_document(
+            r#"<img src="/img/pico/18244/screen-small.jpg" alt="Liste">
+            <img src="/img/pico/18244/screen-large.jpg">
+            <img src="/img/pico/41227/screen.jpg" alt="Autre module">"#,
+        );
+
+        let urls: Vec<String> = extract_images(&document, &rules)
+            .into_iter()
+            .map(|image| image.url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://addons.prestashop.com/img/pico/18244/screen-small.jpg",
+                "https://addons.prestashop.com/img/pico/41227/screen.jpg",
+            ]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use scraper::{Html, Selector};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::extractors::ps_addons::extract_reviews::Review;
+
+/// Product data read from the schema.org `Product` JSON-LD block of a product page.
+///
+/// Every field is optional, the DOM and regex extractors are used as fallbacks
+/// when a value is missing from the structured data.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct JsonLdProduct {
+    pub name: Option<String>,
+    pub sku: Option<String>,
+    pub brand: Option<String>,
+    pub price: Option<String>,
+    pub price_currency: Option<String>,
+    /// Price before discount, from a `StrikethroughPrice` or `ListPrice` price specification.
+    pub list_price: Option<String>,
+    /// Date until which the price is valid, usually the end of a promotion.
+    pub price_valid_until: Option<String>,
+    pub availability: Option<String>,
+    pub rating_value: Option<f64>,
+    pub review_count: Option<u32>,
+    pub reviews: Vec<Review>,
+}
+
+/// Extracts the schema.org `Product`, `Offer` and `AggregateRating` data from the
+/// `application/ld+json` scripts of the HTML content.
+///
+/// Returns an empty `JsonLdProduct` if no `Product` is found.
+pub fn extract_json_ld_product(document: &Html) -> JsonLdProduct {
+    let selector = Selector::parse("script[type='application/ld+json']").unwrap();
+
+    for element in document.select(&selector) {
+        let script_content = element.text().collect::<Vec<_>>().join("");
+        let data: Value = serde_json::from_str(&script_content).unwrap_or(Value::Null);
+
+        if let Some(product) = find_product(&data) {
+            return parse_product(product);
+        }
+    }
+
+    JsonLdProduct::default()
+}
+
+/// Searches a JSON-LD value for an object of type `Product`, including arrays and `@graph`.
+fn find_product(value: &Value) -> Option<&Map<String, Value>> {
+    match value {
+        Value::Array(items) => items.iter().find_map(find_product),
+        Value::Object(obj) => {
+            if has_type(obj, "Product") {
+                Some(obj)
+            } else {
+                obj.get("@graph").and_then(find_product)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Checks the `@type` of a JSON-LD object, which can be a string or an array of strings.
+fn has_type(obj: &Map<String, Value>, expected: &str) -> bool {
+    match obj.get("@type") {
+        Some(Value::String(t)) => t == expected,
+        Some(Value::Array(types)) => types.iter().any(|t| t.as_str() == Some(expected)),
+        _ => false,
+    }
+}
+
+fn parse_product(product: &Map<String, Value>) -> JsonLdProduct {
+    // Brand can be a plain string or a `Brand`/`Organization` object
+    let brand = product.get("brand").and_then(|brand| match brand {
+        Value::Object(obj) => obj.get("name").and_then(value_to_string),
+        other => value_to_string(other),
+    });
+
+    // Offers can be a single `Offer`, an array of offers or an `AggregateOffer`
+    let offer = match product.get("offers") {
+        Some(Value::Array(offers)) => offers.first().and_then(|o| o.as_object()),
+        Some(Value::Object(obj)) => Some(obj),
+        _ => None,
+    };
+
+    let price = offer.and_then(|o| {
+        o.get("price")
+            .or_else(|| o.get("lowPrice"))
+            .and_then(value_to_string)
+    });
+    let price_currency = offer.and_then(|o| o.get("priceCurrency").and_then(value_to_string));
+    let price_valid_until = offer.and_then(|o| o.get("priceValidUntil").and_then(value_to_string));
+
+    // A discounted offer gives the price before discount as a price specification
+    let list_price = offer.and_then(|o| match o.get("priceSpecification") {
+        Some(Value::Array(items)) => items.iter().find_map(parse_list_price),
+        Some(item) => parse_list_price(item),
+        None => None,
+    });
+
+    // Keep only the schema.org item name, e.g. "InStock" for "https://schema.org/InStock"
+    let availability = offer
+        .and_then(|o| o.get("availability").and_then(value_to_string))
+        .map(|a| a.rsplit('/').next().unwrap_or_default().to_string());
+
+    let rating = product.get("aggregateRating").and_then(|r| r.as_object());
+    let rating_value = rating
+        .and_then(|r| r.get("ratingValue").and_then(value_to_string))
+        .and_then(|v| v.replace(',', ".").parse::<f64>().ok());
+    let review_count = rating
+        .and_then(|r| {
+            r.get("reviewCount")
+                .or_else(|| r.get("ratingCount"))
+                .and_then(value_to_string)
+        })
+        .and_then(|v| v.parse::<u32>().ok());
+
+    // Individual reviews, the JSON-LD doesn't contain the developer replies
+    let reviews = match product.get("review") {
+        Some(Value::Array(items)) => items.iter().filter_map(parse_review).collect(),
+        Some(item) => parse_review(item).into_iter().collect(),
+        None => Vec::new(),
+    };
+
+    JsonLdProduct {
+        name: product.get("name").and_then(value_to_string),
+        sku: product.get("sku").and_then(value_to_string),
+        brand,
+        price,
+        price_currency,
+        list_price,
+        price_valid_until,
+        availability,
+        rating_value,
+        review_count,
+        reviews,
+    }
+}
+
+fn parse_review(value: &Value) -> Option<Review> {
+    let review = value.as_object()?;
+    let text = review.get("reviewBody").and_then(value_to_string)?;
+
+    let author = review
+        .get("author")
+        .and_then(|author| match author {
+            Value::Object(obj) => obj.get("name").and_then(value_to_string),
+            other => value_to_string(other),
+        })
+        .unwrap_or_default();
+
+    let rating = review
+        .get("reviewRating")
+        .and_then(|r| r.get("ratingValue"))
+        .and_then(value_to_string)
+        .and_then(|v| v.replace(',', ".").parse::<f64>().ok());
+
+    Some(Review {
+        author,
+        date: review
+            .get("datePublished")
+            .and_then(value_to_string)
+            .unwrap_or_default(),
+        rating,
+        text,
+        developer_reply: None,
+    })
+}
+
+/// Returns the price of a `StrikethroughPrice` or `ListPrice` price specification.
+fn parse_list_price(value: &Value) -> Option<String> {
+    let price_type = value.get("priceType").and_then(value_to_string)?;
+    if price_type.ends_with("StrikethroughPrice") || price_type.ends_with("ListPrice") {
+        value.get("price").and_then(value_to_string)
+    } else {
+        None
+    }
+}
+
+/// Converts a JSON string or number into a trimmed, non-empty `String`.
+fn value_to_string(value: &Value) -> Option<String> {
+    let s = match value {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
@@ -1,14 +1,10 @@
-use scraper::{Html, Selector};
+use scraper::Html;
 
-pub fn extract_module_version(html_content: &str) -> String {
-    let document = Html::parse_document(html_content);
-    let selector =
-        Selector::parse("span.muik-about-module__title-version.puik-body-default").unwrap();
+use crate::extractors::rules::ExtractorRules;
 
-    // Select the first span with the specified class and extract its textual content
-    document
-        .select(&selector)
-        .next()
-        .map(|element| element.text().collect::<Vec<_>>().join(""))
+/// Extracts the module version using the `module_version` rule.
+pub fn extract_module_version(document: &Html, rules: &ExtractorRules) -> String {
+    rules
+        .extract("module_version", document)
         .unwrap_or_default() // Returns an empty string if None
 }
@@ -0,0 +1,11 @@
+use scraper::Html;
+
+use crate::extractors::rules::ExtractorRules;
+
+/// Extracts the ISO 4217 code of the currency of the displayed price, e.g. "EUR",
+/// using the `price_currency` rule.
+pub fn extract_price_currency(document: &Html, rules: &ExtractorRules) -> Option<String> {
+    rules
+        .extract("price_currency", document)
+        .map(|currency| currency.trim().to_uppercase())
+}
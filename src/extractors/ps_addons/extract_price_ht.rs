@@ -1,11 +1,9 @@
-use regex::Regex;
+use scraper::Html;
 
-/// Extracts the price HT from the HTML content as a string, returns empty string if not found.
-pub fn extract_price_ht(html_content: &str) -> String {
-    let regex = Regex::new(r#""price":(\d+(\.\d+)?)?"#).unwrap();
+use crate::extractors::rules::ExtractorRules;
 
-    regex
-        .captures(html_content)
-        .and_then(|caps| caps.get(1).map(|match_| match_.as_str().to_string()))
-        .unwrap_or_default() // Return an empty string if no price is found
+/// Extracts the price HT from the HTML content as a string using the `price_ht` rule,
+/// returns empty string if not found.
+pub fn extract_price_ht(document: &Html, rules: &ExtractorRules) -> String {
+    rules.extract("price_ht", document).unwrap_or_default() // Return an empty string if no price is found
 }
@@ -1,24 +1,13 @@
-use regex::Regex;
-use scraper::{Html, Selector};
+use scraper::Html;
 
-/// Extracts the SKU (Stock Keeping Unit) from the HTML content by searching for the pattern `,"sku":<number>,`.
-/// Returns 0 if no SKU is found.
-pub fn extract_product_id(html_content: &str) -> u32 {
-    let document = Html::parse_document(html_content);
-    let selector = Selector::parse("script").unwrap();
-    let regex = Regex::new(r#","sku":(\d+),"#).unwrap();
+use crate::extractors::rules::ExtractorRules;
 
-    // Iterate over script elements to find the script containing the SKU
-    document
-        .select(&selector)
-        .find_map(|element| {
-            element.text().find_map(|script_text| {
-                // Apply regex to extract the SKU
-                regex.captures(script_text).and_then(|caps| {
-                    caps.get(1)
-                        .map(|match_| match_.as_str().parse::<u32>().unwrap_or(0))
-                })
-            })
-        })
+/// Extracts the SKU (Stock Keeping Unit) from the HTML content using the `product_id` rule,
+/// by default the pattern `,"sku":<number>,` in the scripts of the page.
+/// Returns 0 if no SKU is found.
+pub fn extract_product_id(document: &Html, rules: &ExtractorRules) -> u32 {
+    rules
+        .extract("product_id", document)
+        .and_then(|sku| sku.trim().parse::<u32>().ok())
         .unwrap_or(0) // Return 0 if no SKU is found
 }
@@ -0,0 +1,86 @@
+use scraper::Html;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::extractors::ps_addons::extract_json_ld_product::JsonLdProduct;
+use crate::extractors::rules::ExtractorRules;
+
+/// A discount running on the product.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Promotion {
+    /// Price excluding tax before the discount.
+    pub regular_price: String,
+    /// Discounted price excluding tax.
+    pub sale_price: String,
+    /// First day of the promotion (`YYYY-MM-DD`), if shown.
+    pub date_on_sale_from: Option<String>,
+    /// Last day of the promotion (`YYYY-MM-DD`), if shown.
+    pub date_on_sale_to: Option<String>,
+}
+
+/// Detects a promotion from the struck-through and current prices of the page.
+///
+/// The prices are read with the `price_regular` and `price_sale` rules, with the
+/// JSON-LD list price and offer price as fallbacks. There is a promotion only if
+/// the regular price is higher than the sale price. The dates are read with the
+/// `sale_start_date` and `sale_end_date` rules, the end date falls back to the
+/// JSON-LD `priceValidUntil`.
+pub fn extract_promotion(
+    document: &Html,
+    rules: &ExtractorRules,
+    json_ld_product: &JsonLdProduct,
+) -> Option<Promotion> {
+    let regular_price = rules
+        .extract("price_regular", document)
+        .or_else(|| json_ld_product.list_price.clone())?;
+    let sale_price = rules
+        .extract("price_sale", document)
+        .or_else(|| json_ld_product.price.clone())?;
+
+    let parse = |price: &str| price.trim().replace(',', ".").parse::<f64>().ok();
+    if parse(&regular_price)? <= parse(&sale_price)? {
+        return None;
+    }
+
+    let date_on_sale_from = rules
+        .extract("sale_start_date", document)
+        .and_then(|date| parse_date(&date));
+    let date_on_sale_to = rules
+        .extract("sale_end_date", document)
+        .or_else(|| json_ld_product.price_valid_until.clone())
+        .and_then(|date| parse_date(&date));
+
+    Some(Promotion {
+        regular_price,
+        sale_price,
+        date_on_sale_from,
+        date_on_sale_to,
+    })
+}
+
+/// Finds a date in a text such as "Jusqu'au 31/12/2024", returns it as `YYYY-MM-DD`.
+///
+/// ISO dates and day-first dates (`31/12/2024`, `31.12.2024`) are supported.
+fn parse_date(text: &str) -> Option<String> {
+    let iso = Regex::new(r"(\d{4})-(\d{1,2})-(\d{1,2})").unwrap();
+    let day_first = Regex::new(r"(\d{1,2})[/.-](\d{1,2})[/.-](\d{4})").unwrap();
+
+    let (year, month, day) = if let Some(caps) = iso.captures(text) {
+        (
+            caps[1].parse().ok()?,
+            caps[2].parse().ok()?,
+            caps[3].parse().ok()?,
+        )
+    } else {
+        let caps = day_first.captures(text)?;
+        (
+            caps[3].parse().ok()?,
+            caps[2].parse().ok()?,
+            caps[1].parse().ok()?,
+        )
+    };
+
+    NaiveDate::from_ymd_opt(year, month, day).map(|date| date.format("%Y-%m-%d").to_string())
+}
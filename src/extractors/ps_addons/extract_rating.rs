@@ -0,0 +1,21 @@
+use scraper::Html;
+
+use crate::extractors::rules::ExtractorRules;
+
+/// Extracts the average rating and the number of reviews displayed on the product page,
+/// using the `rating_value` and `review_count` rules.
+///
+/// Used as a fallback when the JSON-LD `AggregateRating` is missing.
+/// Returns `None` for each value that can't be found.
+pub fn extract_rating(document: &Html, rules: &ExtractorRules) -> (Option<f64>, Option<u32>) {
+    let rating_value = rules
+        .extract("rating_value", document)
+        .and_then(|value| value.parse::<f64>().ok());
+
+    let review_count = rules
+        .extract("review_count", document)
+        .and_then(|value| value.parse::<f64>().ok())
+        .map(|value| value as u32);
+
+    (rating_value, review_count)
+}
@@ -0,0 +1,52 @@
+use scraper::Html;
+use serde::Serialize;
+
+use crate::extractors::rules::ExtractorRules;
+use crate::utilities::extract_id_from_url::extract_id_from_url;
+
+/// PrestaShop product ids of the modules suggested on the product page.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct RelatedModules {
+    /// "Related modules", imported as WooCommerce upsells.
+    pub upsells: Vec<u32>,
+    /// "Frequently bought together", imported as WooCommerce cross-sells.
+    pub cross_sells: Vec<u32>,
+}
+
+/// Extracts the related and frequently bought together modules of the product page.
+///
+/// The elements matched by the `related_module` and `bought_together` rules are read
+/// for a `data-id-product` or `data-product-id` attribute, then for the id in their
+/// `href` link. Each id is returned once, in page order.
+pub fn extract_related_modules(document: &Html, rules: &ExtractorRules) -> RelatedModules {
+    RelatedModules {
+        upsells: extract_product_ids(document, rules, "related_module"),
+        cross_sells: extract_product_ids(document, rules, "bought_together"),
+    }
+}
+
+fn extract_product_ids(document: &Html, rules: &ExtractorRules, name: &str) -> Vec<u32> {
+    let mut ids: Vec<u32> = Vec::new();
+
+    for element in rules.select(name, document.root_element()) {
+        let id = ["data-id-product", "data-product-id"]
+            .iter()
+            .filter_map(|attribute| element.value().attr(attribute))
+            .find_map(|value| value.trim().parse::<u32>().ok())
+            .or_else(|| {
+                element
+                    .value()
+                    .attr("href")
+                    .map(extract_id_from_url)
+                    .filter(|id| *id != 0)
+            });
+
+        if let Some(id) = id {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    ids
+}
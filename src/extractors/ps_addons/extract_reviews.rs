@@ -0,0 +1,45 @@
+use scraper::Html;
+use serde::Serialize;
+
+use crate::extractors::rules::ExtractorRules;
+
+/// A customer review of a product, with the developer reply if there is one.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Review {
+    pub author: String,
+    pub date: String,
+    pub rating: Option<f64>,
+    pub text: String,
+    pub developer_reply: Option<String>,
+}
+
+/// Extracts the customer reviews listed on the product page.
+///
+/// Each element matched by the `review` rule is a review, its fields are read
+/// with the `review_*` rules. Returns an empty vector if the page has no reviews.
+pub fn extract_reviews(document: &Html, rules: &ExtractorRules) -> Vec<Review> {
+    let mut reviews = Vec::new();
+
+    for element in rules.select("review", document.root_element()) {
+        // Skip blocks without content, e.g. the empty review form
+        let Some(text) = rules.extract_from("review_text", element) else {
+            continue;
+        };
+
+        reviews.push(Review {
+            author: rules
+                .extract_from("review_author", element)
+                .unwrap_or_default(),
+            date: rules
+                .extract_from("review_date", element)
+                .unwrap_or_default(),
+            rating: rules
+                .extract_from("review_rating", element)
+                .and_then(|rating| rating.parse::<f64>().ok()),
+            text,
+            developer_reply: rules.extract_from("review_developer_reply", element),
+        });
+    }
+
+    reviews
+}
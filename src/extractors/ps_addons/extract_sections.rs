@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+use scraper::Html;
+use serde::Serialize;
+
+use crate::extractors::rules::ExtractorRules;
+
+/// A section of the product description, identified by its heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionKind {
+    Description,
+    Features,
+    Installation,
+    Support,
+    Faq,
+}
+
+impl SectionKind {
+    pub const ALL: [SectionKind; 5] = [
+        SectionKind::Description,
+        SectionKind::Features,
+        SectionKind::Installation,
+        SectionKind::Support,
+        SectionKind::Faq,
+    ];
+
+    /// Name of the extractor rule of the section, also used in meta keys.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SectionKind::Description => "description",
+            SectionKind::Features => "features",
+            SectionKind::Installation => "installation",
+            SectionKind::Support => "support",
+            SectionKind::Faq => "faq",
+        }
+    }
+
+    /// Heading used when the section is rendered in another field.
+    pub fn title(&self) -> &'static str {
+        match self {
+            SectionKind::Description => "Description",
+            SectionKind::Features => "Features",
+            SectionKind::Installation => "Installation",
+            SectionKind::Support => "Support",
+            SectionKind::Faq => "FAQ",
+        }
+    }
+}
+
+/// The HTML content of each section found on the page.
+pub type ProductSections = BTreeMap<SectionKind, String>;
+
+/// Extracts the sections of the product description.
+///
+/// Each section is read with the rule of the same name, which matches a
+/// `product-description__title` heading by its labels and reads the content
+/// block following it, so the sections can appear in any order. Sections
+/// without a matching heading are left out.
+pub fn extract_sections(document: &Html, rules: &ExtractorRules) -> ProductSections {
+    SectionKind::ALL
+        .iter()
+        .filter_map(|kind| {
+            rules
+                .extract_from(kind.name(), document.root_element())
+                .map(|content| (*kind, content))
+        })
+        .collect()
+}
@@ -0,0 +1,31 @@
+use scraper::Html;
+
+use serde::Serialize;
+
+use crate::extractors::rules::ExtractorRules;
+use crate::utilities::ps_version::{parse_ps_version_range, PsVersionRange};
+
+/// The details only shown on theme pages.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ThemeDetails {
+    /// URL of the live demo of the theme.
+    pub demo_url: Option<String>,
+    /// Layout features, e.g. "Méga menu".
+    pub layout_features: Vec<String>,
+    /// Supported PrestaShop versions, if listed on the theme block.
+    pub ps_versions: Option<PsVersionRange>,
+}
+
+/// Extracts the demo URL, layout features and supported versions of a theme page,
+/// using the `theme_demo_url`, `theme_feature` and `theme_ps_versions` rules.
+pub fn extract_theme(document: &Html, rules: &ExtractorRules) -> ThemeDetails {
+    ThemeDetails {
+        demo_url: rules
+            .extract("theme_demo_url", document)
+            .map(|url| url.trim().to_string()),
+        layout_features: rules.extract_all("theme_feature", document),
+        ps_versions: rules
+            .extract("theme_ps_versions", document)
+            .and_then(|versions| parse_ps_version_range(&versions)),
+    }
+}
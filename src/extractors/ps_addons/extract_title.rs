@@ -1,12 +1,126 @@
-use scraper::{Html, Selector};
+use scraper::Html;
 
-pub fn extract_title(html_content: &str) -> String {
-    let document = Html::parse_document(html_content);
-    let selector = Selector::parse("title").unwrap();
+use std::sync::Arc;
 
-    document
-        .select(&selector)
-        .next()
-        .map(|n| n.inner_html())
+use anyhow::{Context, Result};
+use htmlentity::entity::{decode, ICodedDataTrait};
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::get_configuration_value;
+use crate::extractors::rules::ExtractorRules;
+
+/// Suffixes stripped from the titles when `title_suffixes` isn't set.
+pub const DEFAULT_TITLE_SUFFIXES: [&str; 2] = [" - PrestaShop Addons", " | PrestaShop Addons"];
+
+/// Extracts the title of the product, from the H1 (`title` rule) or else the
+/// `<title>` of the page (`page_title` rule), normalized with [`normalize_title`].
+pub fn extract_title(document: &Html, rules: &ExtractorRules, suffixes: &[String]) -> String {
+    ["title", "page_title"]
+        .iter()
+        .filter_map(|rule| rules.extract(rule, document))
+        .map(|title| normalize_title(&title, suffixes))
+        .find(|title| !title.is_empty())
         .unwrap_or_else(|| "No title found".to_string())
 }
+
+/// Decodes the HTML entities, collapses the whitespace and removes the first
+/// matching suffix (case insensitive), e.g. " - PrestaShop Addons".
+pub fn normalize_title(title: &str, suffixes: &[String]) -> String {
+    // Titles may be encoded twice, e.g. "&amp;amp;" in the JSON-LD data
+    let mut title = title.to_string();
+    for _ in 0..2 {
+        match decode(title.as_bytes()).to_string() {
+            Ok(decoded) if decoded != title => title = decoded,
+            _ => break,
+        }
+    }
+
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    suffixes
+        .iter()
+        .filter(|suffix| !suffix.trim().is_empty())
+        .find_map(|suffix| {
+            let start = title.len().checked_sub(suffix.len())?;
+            title
+                .get(start..)?
+                .eq_ignore_ascii_case(suffix)
+                .then(|| title[..start].trim())
+        })
+        .filter(|stripped| !stripped.is_empty())
+        .unwrap_or(&title)
+        .to_string()
+}
+
+/// Reads the title suffixes to strip from the `title_suffixes` configuration.
+pub async fn load_title_suffixes(db: &Arc<Mutex<Connection>>) -> Result<Vec<String>> {
+    let value = get_configuration_value(db, "title_suffixes").await?;
+    serde_json::from_str(&value).context("Failed to parse title_suffixes configuration")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_suffixes() -> Vec<String> {
+        DEFAULT_TITLE_SUFFIXES.map(String::from).to_vec()
+    }
+
+    #[test]
+    fn strips_suffix_and_decodes_entities() {
+        let suffixes = default_suffixes();
+
+        assert_eq!(
+            normalize_title("Blog Pro &amp; Actualités - PrestaShop Addons", &suffixes),
+            "Blog Pro & Actualités"
+        );
+        assert_eq!(
+            normalize_title("Blog Pro &amp;amp; Actualités", &suffixes),
+            "Blog Pro & Actualités"
+        );
+        assert_eq!(
+            normalize_title(
+                "  Module\n  Paiement &#233;clair | prestashop addons ",
+                &suffixes
+            ),
+            "Module Paiement éclair"
+        );
+    }
+
+    #[test]
+    fn keeps_title_without_suffix() {
+        let suffixes = vec!["Addons".to_string(), String::new()];
+
+        assert_eq!(
+            normalize_title("Warehouse - Thème multi-usage", &suffixes),
+            "Warehouse - Thème multi-usage"
+        );
+        // The suffix alone isn't stripped
+        assert_eq!(normalize_title(" Addons ", &suffixes), "Addons");
+    }
+
+    #[test]
+    fn prefers_h1_over_page_title() {
+        let rules = ExtractorRules::defaults().unwrap();
+        let suffixes = default_suffixes();
+
+        let html = "<html><head><title>Module A &amp; B - PrestaShop Addons</title></head>\
+            <body><h1> Module A &amp; B </h1></body></html>";
+        assert_eq!(
+            extract_title(&Html::parse_document(html), &rules, &suffixes),
+            "Module A & B"
+        );
+
+        let html = "<html><head><title>Module A &amp; B - PrestaShop Addons</title></head></html>";
+        assert_eq!(
+            extract_title(&Html::parse_document(html), &rules, &suffixes),
+            "Module A & B"
+        );
+
+        assert_eq!(
+            extract_title(&Html::parse_document("<html></html>"), &rules, &suffixes),
+            "No title found"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use regex::Regex;
+use scraper::Html;
+use serde::Serialize;
+
+use crate::extractors::rules::ExtractorRules;
+
+/// A video embedded or linked in the product page.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProductVideo {
+    /// `youtube` or `vimeo`.
+    pub provider: String,
+    pub video_id: String,
+    /// Canonical URL of the video page.
+    pub url: String,
+    /// URL of the embeddable player.
+    pub embed_url: String,
+}
+
+/// Extracts the YouTube and Vimeo videos embedded or linked in the product page.
+///
+/// The elements matched by the `video` rule are checked for a video URL in their
+/// `src`, `data-src`, `href` or `data-video-url` attribute. Channel and profile
+/// links are ignored, each video is returned once, in page order.
+pub fn extract_videos(document: &Html, rules: &ExtractorRules) -> Vec<ProductVideo> {
+    let mut videos: Vec<ProductVideo> = Vec::new();
+
+    for element in rules.select("video", document.root_element()) {
+        let video = ["src", "data-src", "href", "data-video-url"]
+            .iter()
+            .filter_map(|attribute| element.value().attr(attribute))
+            .find_map(parse_video_url);
+
+        if let Some(video) = video {
+            if !videos
+                .iter()
+                .any(|v| v.provider == video.provider && v.video_id == video.video_id)
+            {
+                videos.push(video);
+            }
+        }
+    }
+
+    videos
+}
+
+/// Parses a YouTube or Vimeo URL, returns `None` if it isn't a video URL.
+pub fn parse_video_url(url: &str) -> Option<ProductVideo> {
+    let youtube = Regex::new(
+        r"(?i)^(?:https?:)?//(?:www\.|m\.)?(?:youtube(?:-nocookie)?\.com/(?:watch\?(?:.*&)?v=|embed/|shorts/|v/)|youtu\.be/)([A-Za-z0-9_-]{11})",
+    )
+    .unwrap();
+    let vimeo =
+        Regex::new(r"(?i)^(?:https?:)?//(?:www\.|player\.)?vimeo\.com/(?:video/)?(\d+)").unwrap();
+
+    let url = url.trim();
+
+    if let Some(caps) = youtube.captures(url) {
+        let video_id = caps[1].to_string();
+        return Some(ProductVideo {
+            provider: "youtube".to_string(),
+            url: format!("https://www.youtube.com/watch?v={}", video_id),
+            embed_url: format!("https://www.youtube-nocookie.com/embed/{}", video_id),
+            video_id,
+        });
+    }
+
+    if let Some(caps) = vimeo.captures(url) {
+        let video_id = caps[1].to_string();
+        return Some(ProductVideo {
+            provider: "vimeo".to_string(),
+            url: format!("https://vimeo.com/{}", video_id),
+            embed_url: format!("https://player.vimeo.com/video/{}", video_id),
+            video_id,
+        });
+    }
+
+    None
+}
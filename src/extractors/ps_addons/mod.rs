@@ -1,14 +1,25 @@
 pub mod extract_breadcrumb;
-pub mod extract_description;
 pub mod extract_developer_name;
-pub mod extract_features;
-pub mod extract_image_urls;
+pub mod extract_developer_profile;
+pub mod extract_images;
+pub mod extract_json_ld_product;
 pub mod extract_last_update;
 pub mod extract_module_version;
 pub mod extract_multistore_compatibility;
 pub mod extract_override;
+pub mod extract_price_currency;
 pub mod extract_price_ht;
 pub mod extract_product_id;
+pub mod extract_promotion;
 pub mod extract_ps_version_required;
 pub mod extract_publication_date;
+pub mod extract_rating;
+pub mod extract_related_modules;
+pub mod extract_reviews;
+pub mod extract_sections;
+pub mod extract_theme;
 pub mod extract_title;
+pub mod extract_videos;
+
+#[cfg(test)]
+mod snapshot_tests;
@@ -0,0 +1,164 @@
+//! Runs every extractor over the saved product pages of `tests/fixtures/ps_addons`
+//! and compares the output with the JSON snapshots of `tests/snapshots/ps_addons`.
+//!
+//! After an intended change, update the snapshots with:
+//!
+//! ```sh
+//! UPDATE_SNAPSHOTS=1 cargo test
+//! ```
+//!
+//! then review the diff of the snapshot files before committing them.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use scraper::Html;
+use serde_json::{json, Value};
+
+use crate::extractors::ps_addons::{
+    extract_breadcrumb, extract_developer_name, extract_developer_profile, extract_images,
+    extract_json_ld_product, extract_last_update, extract_module_version,
+    extract_multistore_compatibility, extract_override, extract_price_currency, extract_price_ht,
+    extract_product_id, extract_promotion, extract_ps_version_required, extract_publication_date,
+    extract_rating, extract_related_modules, extract_reviews, extract_sections, extract_theme,
+    extract_title, extract_videos,
+};
+use crate::extractors::rules::ExtractorRules;
+use crate::utilities::product_kind::classify_page;
+use crate::utilities::ps_version::parse_ps_version_range;
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ps_addons")
+}
+
+fn snapshots_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/ps_addons")
+}
+
+/// Runs each extractor on its own, so a snapshot shows which one is affected by a markup change.
+fn extract_all(html: &str, rules: &ExtractorRules) -> Value {
+    let document = Html::parse_document(html);
+    let (rating_value, review_count) = extract_rating::extract_rating(&document, rules);
+    let json_ld_product = extract_json_ld_product::extract_json_ld_product(&document);
+    let ps_version_required =
+        extract_ps_version_required::extract_ps_version_required(&document, rules);
+    let breadcrumbs = extract_breadcrumb::extract_breadcrumb(&document);
+
+    json!({
+        "title": extract_title::extract_title(
+            &document,
+            rules,
+            &extract_title::DEFAULT_TITLE_SUFFIXES.map(String::from)
+        ),
+        "product_id": extract_product_id::extract_product_id(&document, rules),
+        "price_ht": extract_price_ht::extract_price_ht(&document, rules),
+        "price_currency": extract_price_currency::extract_price_currency(&document, rules),
+        "developer_name": extract_developer_name::extract_developer_name(&document, rules),
+        "developer_url": extract_developer_name::extract_developer_url(&document, rules),
+        "developer_profile": extract_developer_profile::extract_developer_profile(&document, rules),
+        "kind": classify_page("", &breadcrumbs, &document, rules),
+        "breadcrumbs": breadcrumbs,
+        "module_version": extract_module_version::extract_module_version(&document, rules),
+        "last_update": extract_last_update::extract_last_update(&document, rules),
+        "multistore_compatibility":
+            extract_multistore_compatibility::extract_multistore_compatibility(&document, rules),
+        "publication_date": extract_publication_date::extract_publication_date(&document, rules),
+        "with_override": extract_override::extract_override(&document, rules),
+        "ps_version_range": parse_ps_version_range(&ps_version_required),
+        "ps_version_required": ps_version_required,
+        "sections": extract_sections::extract_sections(&document, rules),
+        "images": extract_images::extract_images(&document, rules),
+        "promotion": extract_promotion::extract_promotion(&document, rules, &json_ld_product),
+        "json_ld_product": json_ld_product,
+        "rating_value": rating_value,
+        "review_count": review_count,
+        "reviews": extract_reviews::extract_reviews(&document, rules),
+        "related_modules": extract_related_modules::extract_related_modules(&document, rules),
+        "videos": extract_videos::extract_videos(&document, rules),
+        "theme": extract_theme::extract_theme(&document, rules),
+    })
+}
+
+/// Lists the fields whose value differs between the snapshot and the extractors output.
+fn diff_fields(expected: &Value, actual: &Value) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let expected = expected.as_object().unwrap_or(&empty);
+    let actual = actual.as_object().unwrap_or(&empty);
+
+    let mut keys: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| expected.get(*key) != actual.get(*key))
+        .map(|key| {
+            format!(
+                "  {}:\n    expected: {}\n    actual:   {}",
+                key,
+                expected.get(key).unwrap_or(&Value::Null),
+                actual.get(key).unwrap_or(&Value::Null)
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn extractors_match_snapshots() {
+    let update = env::var("UPDATE_SNAPSHOTS").is_ok_and(|value| value == "1");
+    let rules = ExtractorRules::defaults().expect("Failed to load built-in rules");
+
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(fixtures_dir())
+        .expect("Failed to read fixtures directory")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty(), "No fixtures found");
+
+    let mut failures = Vec::new();
+
+    for fixture in fixtures {
+        let name = fixture.file_stem().unwrap().to_string_lossy().to_string();
+        let html = fs::read_to_string(&fixture).expect("Failed to read fixture");
+        let actual = extract_all(&html, &rules);
+        let snapshot_path = snapshots_dir().join(format!("{}.json", name));
+
+        if update {
+            let content = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+            fs::write(&snapshot_path, content).expect("Failed to write snapshot");
+            continue;
+        }
+
+        match fs::read_to_string(&snapshot_path) {
+            Ok(content) => {
+                let expected: Value =
+                    serde_json::from_str(&content).expect("Failed to parse snapshot");
+                let diff = diff_fields(&expected, &actual);
+                if !diff.is_empty() {
+                    failures.push(format!("{}:\n{}", name, diff.join("\n")));
+                }
+            }
+            Err(_) => failures.push(format!("{}: missing snapshot {:?}", name, snapshot_path)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Extractors output differs from the snapshots:\n{}\n\nRun `UPDATE_SNAPSHOTS=1 cargo test` to accept the changes.",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn every_snapshot_has_a_fixture() {
+    for entry in fs::read_dir(snapshots_dir()).expect("Failed to read snapshots directory") {
+        let path = entry.unwrap().path();
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        assert!(
+            fixtures_dir().join(format!("{}.html", name)).exists(),
+            "Snapshot without fixture: {:?}",
+            path
+        );
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Context, Result};
+use htmlentity::entity::{decode, ICodedDataTrait};
+use regex::Regex;
+use rusqlite::Connection;
+use scraper::element_ref::ElementRef;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::get_configuration_value;
+
+/// Built-in rules for the PrestaShop Addons product pages.
+const DEFAULT_RULES: &str = include_str!("ps_addons/default_rules.toml");
+
+/// An extractor rule as described in the rules file.
+///
+/// The steps are applied in order: CSS `selector` (optionally the element
+/// following a `labels` match), `attribute` or text/HTML content, `json_path`,
+/// `regex`, then the `post_process` steps. The `fallback` rule is applied when
+/// the rule finds nothing.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rule {
+    /// CSS selector of the element holding the value, the whole page is used if empty.
+    pub selector: Option<String>,
+    /// Texts identifying a label element, the value is read from the element following it.
+    pub labels: Vec<String>,
+    /// CSS selector the element following the label must match.
+    pub sibling: Option<String>,
+    /// Index of the matching element to use, the first one with a value if not set.
+    pub index: Option<usize>,
+    /// Attribute to read instead of the text content.
+    pub attribute: Option<String>,
+    /// Read the inner HTML instead of the text content.
+    pub html: bool,
+    /// Dotted path in the JSON content of the element, e.g. `offers.0.price`.
+    pub json_path: Option<String>,
+    /// Regex applied to the value, the first capture group is kept if there is one.
+    pub regex: Option<String>,
+    /// Post-processing steps: `trim`, `strip_divs`, `collapse_whitespace`,
+    /// `decode_entities`, `lowercase`, `number`.
+    pub post_process: Vec<String>,
+    /// Name of the rule applied when this one finds nothing.
+    pub fallback: Option<String>,
+}
+
+struct CompiledRule {
+    rule: Rule,
+    selector: Option<Selector>,
+    sibling: Option<Selector>,
+    regex: Option<Regex>,
+}
+
+/// The set of extractor rules, indexed by field name.
+pub struct ExtractorRules {
+    rules: HashMap<String, CompiledRule>,
+}
+
+const POST_PROCESS_STEPS: [&str; 6] = [
+    "trim",
+    "strip_divs",
+    "collapse_whitespace",
+    "decode_entities",
+    "lowercase",
+    "number",
+];
+
+/// The first number of a value for the `number` step, with a decimal point or comma.
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+(?:[.,]\d+)?").unwrap());
+
+impl ExtractorRules {
+    /// Returns the built-in rules.
+    pub fn defaults() -> Result<Self> {
+        Self::from_toml(DEFAULT_RULES).context("Failed to parse built-in extractor rules")
+    }
+
+    /// Parses and compiles rules from TOML content, one table per field.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let raw: HashMap<String, Rule> = toml::from_str(content)?;
+        let mut rules = HashMap::new();
+
+        for (name, rule) in raw {
+            rules.insert(name.clone(), compile(&name, rule)?);
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Returns the built-in rules, with the fields defined in the file replacing the defaults.
+    pub fn with_overrides(content: &str) -> Result<Self> {
+        let mut rules = Self::defaults()?;
+        let overrides = Self::from_toml(content)?;
+        rules.rules.extend(overrides.rules);
+        Ok(rules)
+    }
+
+    /// Applies the rule of a field to the parsed page.
+    pub fn extract(&self, name: &str, document: &Html) -> Option<String> {
+        self.extract_from(name, document.root_element())
+    }
+
+    /// Applies the rule of a field within an element, e.g. a review block.
+    ///
+    /// The fallback rules are applied in turn until one finds a value.
+    pub fn extract_from(&self, name: &str, root: ElementRef) -> Option<String> {
+        let mut name = name;
+        // A fallback chain looping on itself ends once every rule is tried
+        for _ in 0..=self.rules.len() {
+            let compiled = self.get(name)?;
+
+            let values: Vec<String> = match &compiled.selector {
+                Some(_) => self
+                    .select(name, root)
+                    .iter()
+                    .map(|element| read_value(&compiled.rule, element))
+                    .collect(),
+                None => vec![root.html()],
+            };
+
+            if let Some(value) = values.iter().find_map(|value| apply_steps(compiled, value)) {
+                return Some(value);
+            }
+            name = compiled.rule.fallback.as_deref()?;
+        }
+        None
+    }
+
+    /// Applies the rule of a field to every matching element, e.g. the items of a list.
+    ///
+    /// Elements whose value is empty are left out. The fallback rules are applied in turn
+    /// until one finds a value.
+    pub fn extract_all(&self, name: &str, document: &Html) -> Vec<String> {
+        let mut name = name;
+        for _ in 0..=self.rules.len() {
+            let Some(compiled) = self.get(name) else {
+                return Vec::new();
+            };
+
+            let values: Vec<String> = self
+                .select(name, document.root_element())
+                .iter()
+                .filter_map(|element| apply_steps(compiled, &read_value(&compiled.rule, element)))
+                .collect();
+
+            match &compiled.rule.fallback {
+                Some(fallback) if values.is_empty() => name = fallback,
+                _ => return values,
+            }
+        }
+        Vec::new()
+    }
+
+    /// Returns the elements matched by the rule of a field within an element.
+    ///
+    /// Only the element at `index` is returned when the rule sets one, none if there are fewer.
+    pub fn select<'a>(&self, name: &str, root: ElementRef<'a>) -> Vec<ElementRef<'a>> {
+        let Some(compiled) = self.get(name) else {
+            return Vec::new();
+        };
+        let Some(selector) = &compiled.selector else {
+            return vec![root];
+        };
+
+        let elements: Vec<ElementRef> = if compiled.rule.labels.is_empty() {
+            root.select(selector).collect()
+        } else {
+            root.select(selector)
+                .filter(|element| {
+                    let text = element.text().collect::<Vec<_>>().join("");
+                    compiled
+                        .rule
+                        .labels
+                        .iter()
+                        .any(|label| text.contains(label))
+                })
+                .filter_map(|label| next_sibling(label, compiled.sibling.as_ref()))
+                .collect()
+        };
+
+        match compiled.rule.index {
+            Some(index) => elements.into_iter().nth(index).into_iter().collect(),
+            None => elements,
+        }
+    }
+
+    /// Returns the rule of a field, the fields without a rule are left empty.
+    fn get(&self, name: &str) -> Option<&CompiledRule> {
+        self.rules.get(name)
+    }
+}
+
+/// The extractor rules last loaded, with the modification time of their file.
+#[derive(Default)]
+pub struct ExtractorRulesCache {
+    loaded: Mutex<Option<LoadedRules>>,
+}
+
+struct LoadedRules {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    rules: Arc<ExtractorRules>,
+}
+
+/// Loads the extractor rules, using the file set in `rules_file` (`[extractors]` section)
+/// to override the built-in rules.
+///
+/// The rules are parsed again only when the file changes, so they can be fixed while the
+/// scraper is running. A relative path is resolved from the directory containing the executable.
+pub async fn load_extractor_rules(
+    db: &Arc<Mutex<Connection>>,
+    cache: &ExtractorRulesCache,
+) -> Result<Arc<ExtractorRules>> {
+    let rules_file = get_configuration_value(db, "extractor_rules").await?;
+
+    let rules_path = if rules_file.is_empty() {
+        None
+    } else {
+        let mut rules_path = PathBuf::from(&rules_file);
+        if rules_path.is_relative() {
+            let mut current_dir =
+                env::current_exe().context("Failed to get current executable path")?;
+            current_dir.pop();
+            rules_path = current_dir.join(rules_path);
+        }
+        Some(rules_path)
+    };
+    let modified = rules_path
+        .as_ref()
+        .map(|rules_path| {
+            fs::metadata(rules_path)
+                .and_then(|metadata| metadata.modified())
+                .context(format!(
+                    "Failed to read extractor rules file: {:?}",
+                    rules_path
+                ))
+        })
+        .transpose()?;
+
+    let mut loaded = cache.loaded.lock().await;
+    if let Some(loaded) = loaded
+        .as_ref()
+        .filter(|loaded| loaded.path == rules_path && loaded.modified == modified)
+    {
+        return Ok(Arc::clone(&loaded.rules));
+    }
+
+    let rules = match &rules_path {
+        None => ExtractorRules::defaults()?,
+        Some(rules_path) => {
+            let content = fs::read_to_string(rules_path).context(format!(
+                "Failed to read extractor rules file: {:?}",
+                rules_path
+            ))?;
+            ExtractorRules::with_overrides(&content).context(format!(
+                "Failed to parse extractor rules file: {:?}",
+                rules_path
+            ))?
+        }
+    };
+    let rules = Arc::new(rules);
+    *loaded = Some(LoadedRules {
+        path: rules_path,
+        modified,
+        rules: Arc::clone(&rules),
+    });
+
+    Ok(rules)
+}
+
+fn compile(name: &str, rule: Rule) -> Result<CompiledRule> {
+    let parse_selector = |selector: &str| {
+        Selector::parse(selector)
+            .map_err(|e| anyhow!("Invalid selector for rule '{}': {:?}", name, e))
+    };
+
+    let selector = rule.selector.as_deref().map(parse_selector).transpose()?;
+    let sibling = rule.sibling.as_deref().map(parse_selector).transpose()?;
+    let regex = rule
+        .regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .context(format!("Invalid regex for rule '{}'", name))?;
+
+    if let Some(step) = rule
+        .post_process
+        .iter()
+        .find(|step| !POST_PROCESS_STEPS.contains(&step.as_str()))
+    {
+        return Err(anyhow!(
+            "Unknown post-processing step for rule '{}': {}",
+            name,
+            step
+        ));
+    }
+
+    Ok(CompiledRule {
+        rule,
+        selector,
+        sibling,
+        regex,
+    })
+}
+
+/// Returns the first element following the label, matching the sibling selector if set.
+fn next_sibling<'a>(label: ElementRef<'a>, sibling: Option<&Selector>) -> Option<ElementRef<'a>> {
+    let mut next_node = label.next_sibling();
+    while let Some(node) = next_node {
+        if let Some(element) = ElementRef::wrap(node) {
+            if sibling.is_none_or(|selector| selector.matches(&element)) {
+                return Some(element);
+            }
+        }
+        next_node = node.next_sibling();
+    }
+    None
+}
+
+fn read_value(rule: &Rule, element: &ElementRef) -> String {
+    if let Some(attribute) = &rule.attribute {
+        element
+            .value()
+            .attr(attribute)
+            .unwrap_or_default()
+            .to_string()
+    } else if rule.html {
+        element.inner_html()
+    } else {
+        element.text().collect::<Vec<_>>().join("")
+    }
+}
+
+/// Applies the JSON path, regex and post-processing steps, returns `None` if the value is empty.
+fn apply_steps(compiled: &CompiledRule, value: &str) -> Option<String> {
+    let mut value = value.to_string();
+
+    if let Some(path) = &compiled.rule.json_path {
+        let json: Value = serde_json::from_str(&value).ok()?;
+        value = json_path(&json, path)?;
+    }
+
+    if let Some(regex) = &compiled.regex {
+        let caps = regex.captures(&value)?;
+        value = caps.get(1).or_else(|| caps.get(0))?.as_str().to_string();
+    }
+
+    for step in &compiled.rule.post_process {
+        value = post_process(step, &value)?;
+    }
+
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn json_path(json: &Value, path: &str) -> Option<String> {
+    let mut current = json;
+    for segment in path.split('.') {
+        current = match current {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            Value::Object(obj) => obj.get(segment)?,
+            _ => return None,
+        };
+    }
+
+    match current {
+        Value::String(s) => Some(s.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn post_process(step: &str, value: &str) -> Option<String> {
+    let processed = match step {
+        "trim" => value.trim().to_string(),
+        "strip_divs" => value.replace("<div>", "").replace("</div>", ""),
+        "collapse_whitespace" => value.split_whitespace().collect::<Vec<_>>().join(" "),
+        "decode_entities" => decode(value.as_bytes()).to_string().ok()?,
+        "lowercase" => value.to_lowercase(),
+        "number" => {
+            // Keep the first number, with a decimal point, e.g. "4,5/5" becomes "4.5"
+            NUMBER.find(value)?.as_str().replace(',', ".")
+        }
+        _ => value.to_string(),
+    };
+    Some(processed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_only_the_nth_match() {
+        let rules = ExtractorRules::from_toml(
+            r#"
+            [second]
+            selector = "li"
+            index = 1
+
+            [first]
+            selector = "li"
+            "#,
+        )
+        .unwrap();
+
+        let document = Html::parse_document("<ul><li>A</li><li></li><li>C</li></ul>");
+        assert_eq!(rules.extract("second", &document), None);
+        assert_eq!(rules.extract("first", &document), Some("A".to_string()));
+
+        let document = Html::parse_document("<ul><li></li><li>B</li></ul>");
+        assert_eq!(rules.extract("second", &document), Some("B".to_string()));
+        assert_eq!(rules.extract("first", &document), Some("B".to_string()));
+    }
+
+    #[test]
+    fn falls_back_to_the_review_rating_text() {
+        let rules = ExtractorRules::from_toml(
+            r#"
+            [review]
+            selector = "div.muik-review"
+
+            [review_rating]
+            selector = ".muik-review__rating"
+            attribute = "data-rating"
+            post_process = ["number"]
+            fallback = "review_rating_text"
+
+            [review_rating_text]
+            selector = ".muik-review__rating"
+            post_process = ["number"]
+            "#,
+        )
+        .unwrap();
+        let document = Html::parse_document(
+            r#"<div class="muik-review"><div class="muik-review__rating" data-rating="4">★★★★</div></div>
+            <div class="muik-review"><div class="muik-review__rating">3/5</div></div>"#,
+        );
+        let ratings: Vec<_> = rules
+            .select("review", document.root_element())
+            .into_iter()
+            .map(|element| rules.extract_from("review_rating", element))
+            .collect();
+
+        assert_eq!(ratings, vec![Some("4".to_string()), Some("3".to_string())]);
+    }
+}
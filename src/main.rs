@@ -1,16 +1,22 @@
 use std::env;
+use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use chrono::Utc;
 use colored::*;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
-use tokio;
-use tokio::time::Instant;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
 
 use crate::config::configuration;
 use crate::config::get_configuration::{
-    get_configuration_value_as_i64, get_configuration_value_as_usize,
+    get_configuration_value_as_i64, get_configuration_value_as_usize, get_worker_id,
 };
 use crate::utilities::database;
+use crate::utilities::database::jobs::{claim_job, job_done_since, release_job, renew_job};
+use crate::utilities::database::schedule::Lease;
 use crate::utilities::sitemap;
 
 mod config;
@@ -19,13 +25,14 @@ mod process;
 mod utilities;
 mod wordpress;
 
-#[derive(Deserialize, Serialize, Debug)]
-struct JsonResponse {
-    status: String,
-    message: String,
-    page_id: Option<i32>,
-    title: Option<String>,
-}
+/// The job loading the configuration, updating the sitemaps and retiring the removed products.
+const STARTUP_JOB: &str = "startup";
+
+/// Duration in seconds of the lease of the start-up jobs, renewed while they run.
+const STARTUP_JOB_LEASE: i64 = 600;
+
+/// Delay between two checks of the start-up jobs run by another worker.
+const STARTUP_JOB_POLL: Duration = Duration::from_secs(10);
 
 #[derive(Deserialize, Serialize, Debug)]
 struct MediaResponse {
@@ -58,6 +65,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     let db = &db_init.conn;
 
+    // Local commands, they only read the database
+    let args: Vec<String> = env::args().collect();
+    if let Some(command) = args.get(1) {
+        match command.as_str() {
+            "compatible" => {
+                let version = args
+                    .get(2)
+                    .context("Usage: ps_add_scrapping compatible <prestashop_version>")?;
+                print_compatible_modules(db, version).await?;
+                return Ok(());
+            }
+            "prices" => {
+                print_product_prices(db).await?;
+                return Ok(());
+            }
+            "developers" => {
+                print_developers(db).await?;
+                return Ok(());
+            }
+            _ => {
+                eprintln!("{}", format!("Unknown command: {}", command).red());
+                return Err(Box::from(anyhow::anyhow!("Unknown command: {}", command)));
+            }
+        }
+    }
+
     // Check if Settings.toml exists in the current working directory
     let mut current_dir = env::current_exe().context("Failed to get current executable path")?;
     current_dir.pop();
@@ -67,37 +100,175 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Err(Box::from(anyhow::anyhow!("Settings.toml file not found")));
     }
 
+    // The start-up jobs run on a single worker, the others wait for them to be done
+    startup_jobs(db, &config_path).await?;
+
+    // Process URLs
+    let batch_size = get_configuration_value_as_usize(db, "batch_size").await?;
+    let max_concurrency = get_configuration_value_as_usize(db, "max_concurrency").await?;
+
+    let start = Instant::now();
+
+    if let Err(e) = process::process_urls_dynamically(db, batch_size, max_concurrency).await {
+        eprintln!("{}", format!("Failed to process URLs: {:?}", e).red());
+        return Err(e.into());
+    }
+
+    let duration = start.elapsed();
+    println!(
+        "{}",
+        format!("Time to process URLs: {:?}", duration).green()
+    );
+
+    Ok(())
+}
+
+/// Runs the start-up jobs on a single worker of those sharing the database.
+///
+/// The worker claiming the start-up job runs it while renewing its lease, the other workers
+/// wait until it is done. The job of a worker that stopped is claimed again once its lease
+/// expires.
+async fn startup_jobs(db: &Arc<Mutex<Connection>>, config_path: &Path) -> Result<()> {
+    let lease = Lease {
+        worker_id: get_worker_id(),
+        duration: STARTUP_JOB_LEASE,
+    };
+    let waiting_since = Utc::now().to_rfc3339();
+
+    loop {
+        if job_done_since(db, STARTUP_JOB, &waiting_since).await? {
+            println!("{}", "Start-up jobs done by another worker".yellow());
+            return Ok(());
+        }
+
+        if claim_job(db, STARTUP_JOB, &lease).await? {
+            // Renew the lease while the jobs run
+            let heartbeat = {
+                let db = Arc::clone(db);
+                let lease = lease.clone();
+                tokio::spawn(async move {
+                    let period = Duration::from_secs(STARTUP_JOB_LEASE as u64 / 3);
+                    loop {
+                        tokio::time::sleep(period).await;
+                        if let Err(e) = renew_job(&db, STARTUP_JOB, &lease).await {
+                            eprintln!(
+                                "{}",
+                                format!("Failed to renew the start-up job lease: {:?}", e).red()
+                            );
+                        }
+                    }
+                })
+            };
+
+            let result = run_startup_jobs(db, config_path).await;
+            heartbeat.abort();
+            release_job(db, STARTUP_JOB, &lease, result.is_ok()).await?;
+            return result;
+        }
+
+        println!(
+            "{}",
+            "Waiting for the start-up jobs of another worker".yellow()
+        );
+        tokio::time::sleep(STARTUP_JOB_POLL).await;
+    }
+}
+
+/// Loads the configuration, updates the sitemaps and retires the products removed from Addons.
+async fn run_startup_jobs(db: &Arc<Mutex<Connection>>, config_path: &Path) -> Result<()> {
     // Load configuration
-    if let Err(e) = configuration::load_configuration(&db, config_path.to_str().unwrap()).await {
+    if let Err(e) = configuration::load_configuration(db, &config_path.to_string_lossy()).await {
         eprintln!("{}", format!("Failed to load configuration: {:?}", e).red());
-        return Err(e.into());
+        return Err(e);
     }
 
     // Update sitemap
     let sitemap_frequency_update =
-        get_configuration_value_as_i64(&db, "sitemap_frequency_update").await?;
+        get_configuration_value_as_i64(db, "sitemap_frequency_update").await?;
 
-    if let Err(e) = sitemap::sitemap_update::sitemap_update(&db, sitemap_frequency_update).await {
+    if let Err(e) = sitemap::sitemap_update::sitemap_update(db, sitemap_frequency_update).await {
         eprintln!("{}", format!("Failed to update sitemap: {:?}", e).red());
-        return Err(e.into());
+        return Err(e);
     }
 
-    // Process URLs
-    let batch_size = get_configuration_value_as_usize(&db, "batch_size").await?;
-    let max_concurrency = get_configuration_value_as_usize(&db, "max_concurrency").await?;
+    // Retire the products removed from Addons
+    if let Err(e) = process::retire_removed_products(db).await {
+        eprintln!(
+            "{}",
+            format!("Failed to retire removed products: {:?}", e).red()
+        );
+        return Err(e);
+    }
 
-    let start = Instant::now();
+    Ok(())
+}
 
-    if let Err(e) = process::process_urls_dynamically(&db, batch_size, max_concurrency).await {
-        eprintln!("{}", format!("Failed to process URLs: {:?}", e).red());
-        return Err(e.into());
-    }
+/// Lists the scraped modules supporting a PrestaShop version, e.g. `compatible 8.1`.
+async fn print_compatible_modules(db: &Arc<Mutex<Connection>>, version: &str) -> Result<()> {
+    let modules = database::products::find_modules_supporting(db, version).await?;
 
-    let duration = start.elapsed();
     println!(
         "{}",
-        format!("Time to process URLs: {:?}", duration).green()
+        format!("{} modules support PrestaShop {}", modules.len(), version).green()
     );
+    for module in modules {
+        println!(
+            "{} | {} | {} - {} | {}",
+            module.ps_product_id,
+            module.title,
+            module.ps_version_min,
+            module.ps_version_max.as_deref().unwrap_or("latest"),
+            module.ps_url
+        );
+    }
+
+    Ok(())
+}
+
+/// Lists the Addons price of the scraped products next to the price computed for WooCommerce.
+async fn print_product_prices(db: &Arc<Mutex<Connection>>) -> Result<()> {
+    let format_price = |price: Option<f64>| {
+        price
+            .map(|price| format!("{:.2}", price))
+            .unwrap_or_else(|| "skipped".to_string())
+    };
+
+    for product in database::products::get_product_prices(db).await? {
+        println!(
+            "{} | {} | {} HT -> {}",
+            product.ps_product_id,
+            product.title,
+            format_price(product.price_ht),
+            format_price(product.price_computed)
+        );
+    }
+
+    Ok(())
+}
+
+/// Lists the scraped developers with their profile and the number of their scraped products.
+async fn print_developers(db: &Arc<Mutex<Connection>>) -> Result<()> {
+    let developers = database::developers::get_developers(db).await?;
+
+    println!("{}", format!("{} developers", developers.len()).green());
+    for developer in developers {
+        println!(
+            "{} | {} | {} modules ({} scraped) | {} | {} | {}",
+            developer.name,
+            developer.country.as_deref().unwrap_or("?"),
+            developer
+                .module_count
+                .map(|count| count.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            developer.scraped_products,
+            developer
+                .rating_value
+                .map(|rating| format!("{:.1}/5", rating))
+                .unwrap_or_else(|| "no rating".to_string()),
+            developer.badges.join(", "),
+            developer.url
+        );
+    }
 
     Ok(())
 }
@@ -3,147 +3,304 @@ use std::sync::Arc;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use colored::Colorize;
-use futures::{stream, StreamExt};
+use futures::{stream, TryStreamExt};
 use regex::Regex;
 use reqwest::Client;
 use rusqlite::{Connection, OptionalExtension, params};
-use serde_json::json;
+use scraper::Html;
+use serde_json::{json, Map, Value};
 use tokio::sync::Mutex;
 use tokio::task;
 
-use crate::config::get_configuration::{get_configuration_value, get_configuration_value_as_i64};
+use crate::config::get_configuration::{
+    get_configuration_value, get_configuration_value_as_bool, get_configuration_value_as_i64,
+    get_flaresolverr_url, get_worker_id,
+};
+use crate::extractors::ps_addons::extract_developer_profile::extract_developer_profile;
+use crate::extractors::ps_addons::extract_sections::SectionKind;
+use crate::extractors::ps_addons::extract_title::load_title_suffixes;
+use crate::extractors::rules::{load_extractor_rules, ExtractorRules, ExtractorRulesCache};
 use crate::utilities::{extract_data, extract_id_from_url};
+use crate::utilities::currency::{convert_price, load_exchange_rates, ExchangeRates};
+use crate::utilities::database::developers::{
+    get_developer, insert_developer_into_sql, set_developer_wc_brand_id, StoredDeveloper,
+};
+use crate::utilities::database::prices::insert_price_into_sql;
+use crate::utilities::database::products::{
+    set_product_developer, set_product_wc_id, update_product_compatibility,
+    update_product_details, update_product_kind, update_product_price,
+};
+use crate::utilities::database::relations::{
+    get_pending_relations, insert_relations_into_sql, set_relations_synced,
+};
+use crate::utilities::database::removals::{
+    get_missing_urls, reset_missing_count, set_url_retired,
+};
+use crate::utilities::database::reviews::{
+    get_pending_reviews, insert_reviews_into_sql, set_review_wc_id,
+};
+use crate::utilities::database::schedule::{
+    claim_due_urls, count_due_urls, release_leases, renew_leases, set_url_disallowed, Lease,
+    Schedule, UrlCursor, UrlPriority,
+};
+use crate::utilities::database::translations::{insert_translation_into_sql, Translation};
+use crate::utilities::database::videos::insert_videos_into_sql;
 use crate::utilities::generate_random_delay::generate_random_delay;
+use crate::utilities::pricing::{compute_price, load_pricing_rules, ComputedPrice, PricingRules};
+use crate::utilities::product_kind::ProductKind;
+use crate::utilities::ps_version::PsVersionRange;
+use crate::utilities::rate_limiter::RateLimiter;
+use crate::utilities::rehost_images::rehost_images;
+use crate::utilities::removed_products::{load_removed_products, RemovedProducts};
+use crate::utilities::render_content::{load_placement, render_section, render_videos, Placement};
+use crate::utilities::robots::load_robots_rules;
+use crate::utilities::sanitize_html::{load_sanitize_options, sanitize_html, SanitizeOptions};
+use crate::utilities::sitemap::sitemap_update::load_sitemap_langs;
+use crate::utilities::translation_plugin::{load_translation_plugin, TranslationPlugin};
 use crate::wordpress::main::{
-    Auth, CreateCategory, CreateProduct, FindCategoryByCustomField, FindProductByCustomField,
+    sale_fields, Auth, CreateCategory, CreateProduct, CreateProductReview, DeleteProduct,
+    FindCategoryByCustomField, FindOrCreateAttribute, FindOrCreateBrand, FindProductByCustomField,
+    ProductAttribute, ProductSale, UpdateProduct,
 };
 
-/// Processes URLs in batches, executing a fixed number of tasks concurrently.
+/// HTTP code recorded for a translation postponed until the product of the main language
+/// exists (425 Too Early), the URL is retried like a failed one.
+const TRANSLATION_POSTPONED: u16 = 425;
+
+/// The settings read once per run, an invalid value fails the run before any URL is scraped.
+struct RunSettings {
+    /// Where the installation, support and FAQ sections are rendered.
+    sections: Placement,
+    /// Where the videos of the page are rendered.
+    videos: Placement,
+    /// Currency of the WooCommerce prices.
+    shop_currency: String,
+    /// Exchange rates of the rates file, `None` if it isn't set.
+    exchange_rates: Option<ExchangeRates>,
+    pricing_rules: PricingRules,
+}
+
+impl RunSettings {
+    async fn load(db: &Arc<Mutex<Connection>>) -> Result<Self> {
+        Ok(Self {
+            sections: load_placement(db, "sections").await?,
+            videos: load_placement(db, "videos").await?,
+            shop_currency: get_configuration_value(db, "shop_currency").await?,
+            exchange_rates: load_exchange_rates(db).await?,
+            pricing_rules: load_pricing_rules(db).await?,
+        })
+    }
+}
+
+/// Processes the due URLs as a continuous work queue, executing a fixed number of tasks concurrently.
+///
+/// The due URLs are streamed by priority with keyset pagination, the next page is read when
+/// the queue runs low, and a new task starts as soon as one ends.
 ///
 /// # Arguments
 ///
 /// * `db` - A shared, locked database connection.
-/// * `batch_size` - The number of URLs to fetch in each batch.
+/// * `batch_size` - The number of URLs to fetch in each page.
 /// * `max_concurrent_tasks` - The maximum number of concurrent tasks.
 ///
 /// # Returns
 ///
-/// `Ok(())` if all URLs are processed successfully, or an error if any task fails.
+/// `Ok(())` once all due URLs are processed, or an error if a setting is invalid or the due
+/// URLs can't be read.
 pub async fn process_urls_dynamically(
     db: &Arc<Mutex<Connection>>,
     batch_size: usize,
     max_concurrent_tasks: usize,
 ) -> Result<()> {
-    let mut offset = 0;
+    let settings = Arc::new(RunSettings::load(db).await?);
 
-    loop {
-        let urls = get_urls_batch(db, offset, batch_size).await?;
+    // The due dates are computed from the start of the run, the URLs processed since are not due
+    let schedule = Schedule {
+        age_url: get_configuration_value_as_i64(db, "age_url").await?,
+        retry_backoff: get_configuration_value_as_i64(db, "retry_backoff").await?,
+        run_start: Utc::now().to_rfc3339(),
+    };
 
-        if urls.is_empty() {
-            break;
+    let counts = count_due_urls(db, &schedule).await?;
+    let count = |priority| {
+        counts
+            .iter()
+            .find(|(due_priority, _)| *due_priority == priority)
+            .map_or(0, |(_, count)| *count)
+    };
+    println!(
+        "{}",
+        format!(
+            "{} URLs due: {} new, {} updated, {} to refresh, {} to retry",
+            counts.iter().map(|(_, count)| count).sum::<usize>(),
+            count(UrlPriority::New),
+            count(UrlPriority::Updated),
+            count(UrlPriority::Due),
+            count(UrlPriority::Retry)
+        )
+            .cyan()
+    );
+
+    // The claimed URLs are leased to this worker, the workers sharing the database skip them
+    let lease = Lease {
+        worker_id: get_worker_id(),
+        duration: get_configuration_value_as_i64(db, "lease_duration").await?,
+    };
+    println!("{}", format!("Worker {}", lease.worker_id).cyan());
+
+    // Renew the leases while the URLs are processed
+    let heartbeat = {
+        let db = Arc::clone(db);
+        let lease = lease.clone();
+        task::spawn(async move {
+            let period = std::time::Duration::from_secs((lease.duration / 3).max(1) as u64);
+            loop {
+                tokio::time::sleep(period).await;
+                if let Err(e) = renew_leases(&db, &lease).await {
+                    eprintln!("{}", format!("Failed to renew the URL leases: {:?}", e).red());
+                }
+            }
+        })
+    };
+
+    // The URLs disallowed by robots.txt are skipped, its Crawl-delay spaces out the requests
+    let robots = load_robots_rules(db).await?;
+    let limiter = Arc::new(RateLimiter::new(db, robots.crawl_delay()));
+    let rules_cache = Arc::new(ExtractorRulesCache::default());
+
+    // Claim the due URLs page by page until none is left
+    let schedule = &schedule;
+    let lease = &lease;
+    let robots = &robots;
+    let pages = stream::try_unfold(UrlCursor::default(), |cursor| {
+        let db = Arc::clone(db);
+        async move {
+            let page = claim_due_urls(&db, schedule, lease, cursor, batch_size).await?;
+            let next = page
+                .iter()
+                .map(UrlCursor::after)
+                .fold(cursor, |next, claimed| next.max(claimed));
+            Ok::<_, anyhow::Error>((!page.is_empty()).then_some((page, next)))
         }
+    });
+    let due_urls = pages
+        .map_ok(|page| stream::iter(page.into_iter().map(Ok::<_, anyhow::Error>)))
+        .try_flatten();
 
-        // Create a stream to process the URLs in parallel
-        let tasks = stream::iter(urls.into_iter().map(|url| {
+    // Keep a fixed number of tasks running at any time
+    let result = due_urls
+        .map_ok(|due| {
             let db = Arc::clone(db);
-            task::spawn(async move {
-                if let Err(e) = process_url(&db, url).await {
-                    eprintln!("Failed to process URL: {:?}", e);
+            let limiter = Arc::clone(&limiter);
+            let rules_cache = Arc::clone(&rules_cache);
+            let settings = Arc::clone(&settings);
+            async move {
+                // A disallowed URL is no longer due, until a sitemap lists it as allowed
+                if !robots.is_allowed(&due.url) {
+                    println!(
+                        "{}",
+                        format!("URL disallowed by robots.txt: {}", due.url).yellow()
+                    );
+                    set_url_disallowed(&db, due.id).await?;
+                    return release_leases(&db, lease, Some(due.id)).await;
                 }
-            })
-        }));
 
-        // Buffer the tasks to ensure a fixed number of tasks are running at any time
-        tasks
-            .buffer_unordered(max_concurrent_tasks)
-            .for_each(|_| async {})
-            .await;
+                let url_db = Arc::clone(&db);
+                let task = task::spawn(async move {
+                    let outcome = process_url(&url_db, &limiter, &rules_cache, &settings, due.url);
+                    if let Err(e) = outcome.await {
+                        eprintln!("Failed to process URL: {:?}", e);
+                    }
+                });
+                if let Err(e) = task.await {
+                    eprintln!("Failed to run URL task: {:?}", e);
+                }
+                release_leases(&db, lease, Some(due.id)).await
+            }
+        })
+        .try_buffer_unordered(max_concurrent_tasks)
+        .try_for_each(|_| async { Ok(()) })
+        .await;
 
-        offset += batch_size;
+    // Release the URLs left claimed if the queue stopped on an error
+    heartbeat.abort();
+    release_leases(db, lease, None).await?;
+    result?;
+
+    // Second pass for the relations whose target was created after the product
+    if get_configuration_value_as_bool(db, "link_related_modules").await? {
+        let wordpress_url = get_configuration_value(db, "wordpress_url").await?;
+        let username_api = get_configuration_value(db, "username_api").await?;
+        let password_api = get_configuration_value(db, "password_api").await?;
+        let wp = Auth::new(wordpress_url, username_api, password_api);
+
+        sync_relations(db, &wp, None).await?;
     }
 
     Ok(())
 }
 
-/// Fetches a batch of URLs from the database.
+/// Processes a given URL, scraping it and creating or updating its WooCommerce product.
+///
+/// The outcome of the URL is recorded once, whatever the step that ended it: the HTTP code
+/// of the scrape or of the failed product creation, 500 when an error left no HTTP code.
 ///
 /// # Arguments
 ///
 /// * `db` - A shared, locked database connection.
-/// * `offset` - The starting point to fetch URLs from.
-/// * `limit` - The maximum number of URLs to fetch.
+/// * `limiter` - The rate limiter of the requests sent to Addons.
+/// * `rules_cache` - The extractor rules last loaded.
+/// * `settings` - The settings read at the start of the run.
+/// * `url` - The URL to be processed.
 ///
 /// # Returns
 ///
-/// A vector of URLs if successful, or an error if the operation fails.
-async fn get_urls_batch(
+/// An empty `Result` if successful, or an error if the operation fails.
+async fn process_url(
     db: &Arc<Mutex<Connection>>,
-    offset: usize,
-    limit: usize,
-) -> Result<Vec<String>> {
-    let db = db.lock().await;
-    let mut stmt = db.prepare("SELECT url FROM urls ORDER BY id LIMIT ?1 OFFSET ?2")?;
-    let rows = stmt.query_map(params![limit, offset], |row| row.get(0))?;
+    limiter: &RateLimiter,
+    rules_cache: &ExtractorRulesCache,
+    settings: &RunSettings,
+    url: String,
+) -> Result<()> {
+    let outcome = scrape_url(db, limiter, rules_cache, settings, &url).await;
 
-    let mut urls = Vec::new();
-    for url in rows {
-        urls.push(url?);
-    }
+    // Update database
+    let http_code = *outcome.as_ref().unwrap_or(&500);
+    let date_modified = Utc::now().to_rfc3339();
+    update_url_in_database(db, &url, &date_modified, http_code).await?;
 
-    Ok(urls)
+    outcome.map(|_| ())
 }
 
-/// Processes a given URL by checking the last modification date and scraping it if necessary.
+/// Scrapes a URL and creates or updates its WooCommerce product.
 ///
 /// # Arguments
 ///
 /// * `db` - A shared, locked database connection.
+/// * `limiter` - The rate limiter of the requests sent to Addons.
+/// * `rules_cache` - The extractor rules last loaded.
+/// * `settings` - The settings read at the start of the run.
 /// * `url` - The URL to be processed.
 ///
 /// # Returns
 ///
-/// An empty `Result` if successful, or an error if the operation fails.
-async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
-    // Checks when URL was last scraped, then doesn't process it if it was scraped recently
-    // If URL was scraped with an HTTP error (e.g., 403, 500), process URL
+/// The HTTP code to record for the URL, or an error if the operation fails.
+async fn scrape_url(
+    db: &Arc<Mutex<Connection>>,
+    limiter: &RateLimiter,
+    rules_cache: &ExtractorRulesCache,
+    settings: &RunSettings,
+    url: &str,
+) -> Result<u16> {
     let age_url = get_configuration_value_as_i64(db, "age_url").await?;
 
-    {
-        let db = db.lock().await;
-        let mut stmt = db.prepare("SELECT date_modified, http_code FROM urls WHERE url = ?1")?;
-        let row = stmt
-            .query_row([url.as_str()], |row| {
-                let date_modified: Option<String> = row.get(0)?;
-                let http_code: i32 = row.get(1).unwrap_or(0);
-                Ok((date_modified, http_code))
-            })
-            .optional()?;
-
-        if let Some((date_modified, http_code)) = row {
-            // Skip URL if it was modified less than `age_url` hours ago and http_code is 200
-            if let Some(date_modified) = date_modified {
-                let last_mod_date = DateTime::parse_from_rfc3339(&date_modified)?;
-                let now = Utc::now();
-                let last_mod_date_utc = last_mod_date.with_timezone(&Utc);
-                let hours_difference = (now - last_mod_date_utc).num_hours();
-
-                if hours_difference <= age_url && http_code == 200 {
-                    println!(
-                        "{}",
-                        format!(
-                            "Skipping URL as it was modified less than {} hours ago: {}",
-                            age_url, url
-                        )
-                            .cyan()
-                    );
-                    return Ok(());
-                }
-            }
-        }
-    }
+    // Load extractor rules before scraping, the rules file can be edited while running
+    let rules = load_extractor_rules(db, rules_cache).await?;
+    let title_suffixes = load_title_suffixes(db).await?;
 
     // Send URL to scraping via FlareSolverr
-    let (status, body) = send_url_to_flaresolverr(db, &url).await?;
+    let (status, body) = send_url_to_flaresolverr(db, limiter, url).await?;
 
     // FlareSolverr scraping failed
     if !status.is_success() {
@@ -156,23 +313,152 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
                 .red()
         );
 
-        // Update database
-        let http_code = status.as_u16();
-        let date_modified = Utc::now().to_rfc3339();
-        update_url_in_database(db, &url, &date_modified, http_code).await?;
-
         // Generate random delay
         generate_random_delay(500, 6000).await;
 
-        return Err(anyhow::anyhow!(
-            "Failed to process URL due to FlareSolverr error"
-        ));
+        return Ok(status.as_u16());
     }
 
     // FlareSolverr scraping success
     println!("{}", "Scraping success".green());
 
-    let extract_data = extract_data::extract_data(&body);
+    let extract_data = extract_data::extract_data(&body, &rules, &title_suffixes);
+
+    // Store the page content in its language, next to the other translations
+    let sitemap_langs = load_sitemap_langs(db).await?;
+    let main_lang = sitemap_langs.first().cloned().unwrap_or_default();
+    let lang = get_url_language(db, url).await?.unwrap_or_else(|| main_lang.clone());
+    if extract_data.product_id != 0 {
+        let translation = Translation {
+            lang: lang.clone(),
+            url: url.to_string(),
+            title: extract_data.title.clone(),
+            description: extract_data.description.clone(),
+            features: extract_data.features.clone(),
+        };
+        insert_translation_into_sql(db, extract_data.product_id, &translation).await?;
+    }
+
+    // Without translation plugin, only the pages of the main language create WooCommerce products
+    let translation_plugin = load_translation_plugin(db).await?;
+    let is_main_lang = lang == main_lang;
+    if !is_main_lang && translation_plugin == TranslationPlugin::None {
+        println!(
+            "{}",
+            format!("Translation stored: {} ({})", extract_data.title, lang).green()
+        );
+
+        // Generate random delay
+        generate_random_delay(1000, 8000).await;
+
+        return Ok(status.as_u16());
+    }
+
+    // Convert the prices to the shop currency and compute the WooCommerce prices
+    let shop_price = |price_ht: &str| {
+        compute_shop_price(
+            price_ht,
+            extract_data.price_currency.as_deref(),
+            &settings.shop_currency,
+            settings.exchange_rates.as_ref(),
+            &settings.pricing_rules,
+        )
+    };
+
+    let price = shop_price(&extract_data.price_ht);
+    println!("{}", format!("Price: {}", price.report()).cyan());
+
+    // A discounted product gets the price before discount as regular price
+    let mut regular_price = price.regular_price();
+    let mut sale: Option<ProductSale> = None;
+    if let Some(promotion) = &extract_data.promotion {
+        let promotion_regular = shop_price(&promotion.regular_price);
+        let promotion_sale = shop_price(&promotion.sale_price);
+
+        if let (Some(regular), Some(sale_price)) =
+            (promotion_regular.computed, promotion_sale.computed)
+        {
+            if sale_price < regular {
+                println!(
+                    "{}",
+                    format!(
+                        "Promotion: {} instead of {}, until {}",
+                        promotion_sale.report(),
+                        promotion_regular.report(),
+                        promotion.date_on_sale_to.as_deref().unwrap_or("unknown")
+                    )
+                        .cyan()
+                );
+                regular_price = promotion_regular.regular_price();
+                sale = Some(ProductSale {
+                    sale_price: promotion_sale.regular_price(),
+                    date_on_sale_from: promotion
+                        .date_on_sale_from
+                        .as_ref()
+                        .map(|date| format!("{}T00:00:00", date)),
+                    date_on_sale_to: promotion
+                        .date_on_sale_to
+                        .as_ref()
+                        .map(|date| format!("{}T23:59:59", date)),
+                });
+            }
+        }
+    }
+
+    // Store compatibility, price, rating, reviews and videos of the main language page
+    if extract_data.product_id != 0 && is_main_lang {
+        update_product_compatibility(
+            db,
+            extract_data.product_id,
+            &extract_data.title,
+            &extract_data.ps_url,
+            &extract_data.ps_version_required,
+            extract_data.ps_version_range.as_ref(),
+        )
+            .await?;
+        update_product_price(db, extract_data.product_id, &price).await?;
+        update_product_details(db, extract_data.product_id, &extract_data).await?;
+        update_product_kind(
+            db,
+            extract_data.product_id,
+            extract_data.kind,
+            extract_data.theme.as_ref().and_then(|theme| theme.demo_url.as_deref()),
+        )
+            .await?;
+        if let (Some(currency), Ok(amount)) = (
+            &extract_data.price_currency,
+            extract_data.price_ht.parse::<f64>(),
+        ) {
+            insert_price_into_sql(db, extract_data.product_id, currency, amount).await?;
+        }
+        insert_reviews_into_sql(
+            db,
+            extract_data.product_id,
+            extract_data.rating_value,
+            extract_data.review_count,
+            &extract_data.reviews,
+        )
+            .await?;
+        insert_videos_into_sql(db, extract_data.product_id, &extract_data.videos).await?;
+        insert_relations_into_sql(db, extract_data.product_id, &extract_data.related_modules)
+            .await?;
+        if let Some(developer_url) = &extract_data.developer_url {
+            set_product_developer(db, extract_data.product_id, developer_url).await?;
+        }
+    }
+
+    // Scrape the developer page, at most once every `age_url` hours
+    let mut developer = None;
+    if let Some(developer_url) = &extract_data.developer_url {
+        if get_configuration_value_as_bool(db, "scrape_developers").await? {
+            match developer_profile(db, limiter, developer_url, &rules, age_url).await {
+                Ok(profile) => developer = profile,
+                Err(e) => {
+                    eprintln!("{}", format!("Failed to scrape developer: {:?}", e).red())
+                }
+            }
+        }
+    }
 
     // Create WooCommerce products using breadcrumbs from scraped data
     let wordpress_url = get_configuration_value(db, "wordpress_url").await?;
@@ -180,22 +466,120 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
     let password_api = get_configuration_value(db, "password_api").await?;
 
     let wp = Arc::new(Auth::new(wordpress_url, username_api, password_api));
+
+    // Sanitize descriptions before sending them to WooCommerce
+    let sanitize_options = load_sanitize_options(db).await?;
+    let mut description = sanitize_html(&extract_data.description, &sanitize_options);
+    let features = sanitize_html(&extract_data.features, &sanitize_options);
+    let rehost = get_configuration_value_as_bool(db, "rehost_images").await?;
+
+    // Render the other sections into the description or meta fields
+    let mut meta_data: Vec<(String, String)> = Vec::new();
+    let sections_meta_prefix = get_configuration_value(db, "sections_meta_prefix").await?;
+    for (kind, content) in &extract_data.sections {
+        if matches!(kind, SectionKind::Description | SectionKind::Features) {
+            continue;
+        }
+        let content = sanitize_html(content, &sanitize_options);
+        match settings.sections {
+            Placement::Description => description.push_str(&render_section(*kind, &content)),
+            Placement::Meta => {
+                meta_data.push((format!("{}{}", sections_meta_prefix, kind.name()), content));
+            }
+            Placement::None => {}
+        }
+    }
+
+    // Render videos into the description or a meta field
+    if !extract_data.videos.is_empty() {
+        let videos = render_videos(&extract_data.videos);
+        match settings.videos {
+            Placement::Description => description.push_str(&videos),
+            Placement::Meta => {
+                let videos_meta_key = get_configuration_value(db, "videos_meta_key").await?;
+                meta_data.push((videos_meta_key, videos));
+            }
+            Placement::None => {}
+        }
+    }
+
+    // Theme demo and layout features are kept in meta fields
+    if let Some(theme) = &extract_data.theme {
+        if let Some(demo_url) = &theme.demo_url {
+            meta_data.push(("ps_theme_demo_url".to_string(), demo_url.clone()));
+        }
+        if !theme.layout_features.is_empty() {
+            meta_data.push(("ps_theme_features".to_string(), theme.layout_features.join(", ")));
+        }
+    }
+
+    // Without breadcrumb, neither the categories nor the product can be created
     let breadcrumbs = &extract_data.breadcrumbs;
-    let last_breadcrumb_index = breadcrumbs.len() - 1;
+    let last_breadcrumb_index = breadcrumbs.len().checked_sub(1);
+    if last_breadcrumb_index.is_none() {
+        println!("{}", format!("No breadcrumb found, product skipped: {}", url).yellow());
+    }
 
     // Process breadcrumb for create category and product
     let mut current_wordpress_parent =
         get_configuration_value_as_i64(db, "wordpress_parent").await?;
 
+    // Themes can be imported under their own root category
+    if extract_data.kind == ProductKind::Theme {
+        let theme_parent = get_configuration_value_as_i64(db, "theme_parent").await?;
+        if theme_parent != 0 {
+            current_wordpress_parent = theme_parent;
+        }
+    }
+    let http_code_re = Regex::new(r"HTTP (\d+):").unwrap();
+    let mut http_code = status.as_u16();
+    let mut wc_product_id: Option<u32> = None;
+
+    // Translations are linked to the product of the main language, which is created first
+    let wc_lang = translation_plugin.lookup_lang(&lang);
+    let mut product_translation = translation_plugin.fields(&lang, None);
+    if !is_main_lang {
+        let original = wp
+            .find_product_by_custom_field(
+                "ps_product_id",
+                &extract_data.product_id.to_string(),
+                Some(&main_lang),
+            )
+            .await?;
+        match original.product_id {
+            Some(original_id) => {
+                product_translation =
+                    translation_plugin.fields(&lang, Some((&main_lang, original_id)));
+            }
+            None => {
+                println!(
+                    "{}",
+                    format!(
+                        "Product not created in {} yet, translation postponed: {}",
+                        main_lang, url
+                    )
+                        .yellow()
+                );
+
+                // Generate random delay
+                generate_random_delay(1000, 8000).await;
+
+                // The retry backoff delays the next attempt
+                return Ok(TRANSLATION_POSTPONED);
+            }
+        }
+    }
+
     for (breadcrumb_index, breadcrumb) in breadcrumbs.iter().enumerate() {
         if let Some(id) = breadcrumb.get("id") {
             // Create product at last breadcrumb
-            if breadcrumb_index == last_breadcrumb_index {
+            if Some(breadcrumb_index) == last_breadcrumb_index {
                 // Check if product exists in WooCommerce
                 match wp
                     .find_product_by_custom_field(
                         "ps_product_id",
                         &extract_data.product_id.to_string(),
+                        wc_lang,
                     )
                     .await
                 {
@@ -209,6 +593,41 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
                                 )
                                     .yellow()
                             );
+                            wc_product_id = product_info.product_id;
+
+                            // Keep the price and promotion of existing products up to date
+                            if let Some(product_id) =
+                                wc_product_id.filter(|_| !regular_price.is_empty())
+                            {
+                                let mut fields = sale_fields(sale.as_ref());
+                                fields.insert("regular_price".to_string(), json!(regular_price));
+                                match wp.update_product(product_id, Value::Object(fields)).await {
+                                    Ok(_) => println!("{}", "Product price updated".green()),
+                                    Err(e) => eprintln!(
+                                        "{}",
+                                        format!("Failed to update product price: {:?}", e).red()
+                                    ),
+                                }
+                            }
+
+                            // Replace the images still hotlinked from Addons by uploaded ones
+                            if let Some(product_id) = wc_product_id.filter(|_| rehost) {
+                                if let Err(e) = rehost_product_images(
+                                    db,
+                                    &wp,
+                                    product_id,
+                                    &description,
+                                    &features,
+                                    &meta_data,
+                                )
+                                    .await
+                                {
+                                    eprintln!(
+                                        "{}",
+                                        format!("Failed to rehost images: {:?}", e).red()
+                                    );
+                                }
+                            }
                             continue;
                         }
                         "notfound" => {
@@ -219,12 +638,24 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
                     Err(e) => eprintln!("Error occurred: {:?}", e),
                 }
 
+                // Free modules can be left out of the import
+                if price.original.is_some() && price.computed.is_none() {
+                    println!("{}", "Free module skipped".yellow());
+                    continue;
+                }
+
+                // Assign the supported PrestaShop major lines as attribute terms
+                let attributes = match &extract_data.ps_version_range {
+                    Some(range) => ps_version_attributes(db, &wp, range).await,
+                    None => Vec::new(),
+                };
+
                 // Create product in WooCommerce
                 println!(
                     "{}",
                     format!(
                         "Creating product: {} | id: {}",
-                        extract_data.title.to_string(),
+                        extract_data.title,
                         extract_data.product_id
                     )
                         .green()
@@ -237,32 +668,50 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
                         "simple".to_string(),
                         true,
                         true,
-                        extract_data.features.to_string(),
-                        extract_data.description.to_string(),
-                        extract_data.price_ht.to_string(),
+                        features.to_string(),
+                        description.to_string(),
+                        regular_price.to_string(),
                         vec![current_wordpress_parent as u32],
-                        &extract_data.image_urls,
+                        &extract_data.images,
                         extract_data.product_id,
                         body.solution.url.to_string(),
+                        &meta_data,
+                        &attributes,
+                        sale.as_ref(),
+                        &product_translation,
                     )
                     .await
                 {
-                    Ok(..) => {
+                    Ok(result) => {
                         println!("{}", "Product created successfully".green());
+                        wc_product_id = result.response_json["id"].as_u64().map(|id| id as u32);
+
+                        // Upload embedded images to WordPress, only once the product exists
+                        if let Some(product_id) = wc_product_id.filter(|_| rehost) {
+                            if let Err(e) = rehost_product_images(
+                                db,
+                                &wp,
+                                product_id,
+                                &description,
+                                &features,
+                                &meta_data,
+                            )
+                                .await
+                            {
+                                eprintln!("{}", format!("Failed to rehost images: {:?}", e).red());
+                            }
+                        }
                     }
                     Err(e) => {
                         eprintln!("{}", "Product created failed".red());
 
-                        // Update database
-                        let re = Regex::new(r"HTTP (\d+):").unwrap();
-                        let http_code = if let Some(cap) = re.captures(&e.to_string()) {
+                        // The URL is recorded as failed, with the HTTP code of the creation
+                        http_code = if let Some(cap) = http_code_re.captures(&e.to_string()) {
                             cap.get(1)
                                 .map_or(500, |m| m.as_str().parse::<u16>().unwrap_or(500))
                         } else {
                             500
                         };
-                        let date_modified = Utc::now().to_rfc3339();
-                        update_url_in_database(db, &url, &date_modified, http_code).await?;
 
                         continue;
                     }
@@ -273,7 +722,7 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
             let id_ps_category = extract_id_from_url::extract_id_from_url(id);
 
             // Check if category exists in WooCommerce
-            match wp.find_category_by_custom_field(id_ps_category).await {
+            match wp.find_category_by_custom_field(id_ps_category, wc_lang).await {
                 Ok(category_info) => match category_info.status.as_ref() {
                     "found" => {
                         println!(
@@ -292,9 +741,22 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
                     "notfound" => {
                         println!("{}", "No category found".cyan());
                         let name = breadcrumb.get("name").unwrap().to_string();
+                        let translation = category_translation(
+                            &wp,
+                            translation_plugin,
+                            id_ps_category,
+                            &lang,
+                            &main_lang,
+                        )
+                            .await;
 
                         match wp
-                            .create_category(name, current_wordpress_parent as u32, id_ps_category)
+                            .create_category(
+                                name,
+                                current_wordpress_parent as u32,
+                                id_ps_category,
+                                &translation,
+                            )
                             .await
                         {
                             Ok(response) => {
@@ -328,22 +790,526 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
         }
     }
 
+    // Import reviews into WooCommerce, the translations share those of the main language
+    if let Some(wc_product_id) = wc_product_id.filter(|_| is_main_lang) {
+        if get_configuration_value_as_bool(db, "import_reviews").await? {
+            if let Err(e) = import_reviews(db, &wp, extract_data.product_id, wc_product_id).await {
+                eprintln!("{}", format!("Failed to import reviews: {:?}", e).red());
+            }
+        }
+
+        // Assign the developer as brand, described with its profile
+        if let Some((developer, refreshed)) = &developer {
+            if get_configuration_value_as_bool(db, "developer_brands").await? {
+                if let Err(e) = assign_developer_brand(
+                    db,
+                    &wp,
+                    wc_product_id,
+                    developer,
+                    *refreshed,
+                    &sanitize_options,
+                )
+                    .await
+                {
+                    eprintln!("{}", format!("Failed to assign brand: {:?}", e).red());
+                }
+            }
+        }
+
+        // Link the related modules already in WooCommerce
+        if extract_data.product_id != 0 {
+            set_product_wc_id(db, extract_data.product_id, wc_product_id).await?;
+            if get_configuration_value_as_bool(db, "link_related_modules").await? {
+                sync_relations(db, &wp, Some(extract_data.product_id)).await?;
+            }
+        }
+    }
+
     // Generate random delay
     generate_random_delay(1000, 8000).await;
 
-    // Update database
-    let date_modified = Utc::now().to_rfc3339();
-    let http_code = status.as_u16();
-    update_url_in_database(db, &url, &date_modified, http_code).await?;
+    Ok(http_code)
+}
+
+/// Uploads the images embedded in the descriptions and meta fields of a created or found
+/// product to the WordPress media library, and updates the product to use them.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `wp` - The WordPress API client.
+/// * `product_id` - The WooCommerce product ID.
+/// * `description` - The description sent to WooCommerce.
+/// * `short_description` - The short description sent to WooCommerce.
+/// * `meta_data` - The meta fields sent to WooCommerce.
+///
+/// # Returns
+///
+/// `Ok(())` if the product is up to date, or an error if the update fails.
+async fn rehost_product_images(
+    db: &Arc<Mutex<Connection>>,
+    wp: &Auth,
+    product_id: u32,
+    description: &str,
+    short_description: &str,
+    meta_data: &[(String, String)],
+) -> Result<()> {
+    let mut fields = Map::new();
+
+    for (field, content) in [
+        ("description", description),
+        ("short_description", short_description),
+    ] {
+        let rehosted = rehost_images(wp, db, content).await;
+        if rehosted != content {
+            fields.insert(field.to_string(), json!(rehosted));
+        }
+    }
+
+    let mut rehosted_meta_data = Vec::new();
+    for (key, value) in meta_data {
+        let rehosted = rehost_images(wp, db, value).await;
+        if rehosted != *value {
+            rehosted_meta_data.push(json!({ "key": key, "value": rehosted }));
+        }
+    }
+    if !rehosted_meta_data.is_empty() {
+        fields.insert("meta_data".to_string(), json!(rehosted_meta_data));
+    }
+
+    // Nothing to update without embedded images
+    if fields.is_empty() {
+        return Ok(());
+    }
+
+    wp.update_product(product_id, Value::Object(fields)).await?;
+    println!("{}", "Product images rehosted".green());
+
+    Ok(())
+}
+
+/// Returns the language fields of a category created in `lang`.
+///
+/// A category in another language than the main one is linked to the category
+/// of the main language with the same PrestaShop ID, if it exists.
+///
+/// # Arguments
+///
+/// * `wp` - The WordPress API client.
+/// * `translation_plugin` - The translation plugin of the shop.
+/// * `ps_category_id` - The PrestaShop category ID.
+/// * `lang` - The language of the category.
+/// * `main_lang` - The main language of the shop.
+async fn category_translation(
+    wp: &Auth,
+    translation_plugin: TranslationPlugin,
+    ps_category_id: u32,
+    lang: &str,
+    main_lang: &str,
+) -> Map<String, Value> {
+    if lang == main_lang {
+        return translation_plugin.fields(lang, None);
+    }
+
+    let original = match wp
+        .find_category_by_custom_field(ps_category_id, Some(main_lang))
+        .await
+    {
+        Ok(category_info) => category_info.category_id,
+        Err(e) => {
+            eprintln!("{}", format!("Failed to find category: {:?}", e).red());
+            None
+        }
+    };
+
+    translation_plugin.fields(lang, original.map(|id| (main_lang, id)))
+}
+
+/// Imports the reviews of a product that are not in WooCommerce yet.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `wp` - The WordPress API client.
+/// * `ps_product_id` - The PrestaShop product ID.
+/// * `wc_product_id` - The WooCommerce product ID.
+///
+/// # Returns
+///
+/// `Ok(())` if all pending reviews are imported, or an error if one of them fails.
+async fn import_reviews(
+    db: &Arc<Mutex<Connection>>,
+    wp: &Auth,
+    ps_product_id: u32,
+    wc_product_id: u32,
+) -> Result<()> {
+    let reviewer_email = get_configuration_value(db, "reviewer_email").await?;
+
+    for pending in get_pending_reviews(db, ps_product_id).await? {
+        let review = pending.review;
+
+        // Developer reply is appended, the reviews API doesn't support replies. The scraped
+        // texts are plain text, escaped before being put in the HTML content
+        let text = quick_xml::escape::escape(review.text.as_str());
+        let content = match &review.developer_reply {
+            Some(reply) => format!(
+                "{}\n\n<blockquote>{}</blockquote>",
+                text,
+                quick_xml::escape::escape(reply.as_str())
+            ),
+            None => text.into_owned(),
+        };
+
+        // Only pass the date if WooCommerce can read it
+        let date_created = DateTime::parse_from_rfc3339(&review.date)
+            .map(|date| date.naive_utc().format("%Y-%m-%dT%H:%M:%S").to_string())
+            .or_else(|_| {
+                chrono::NaiveDate::parse_from_str(&review.date, "%Y-%m-%d")
+                    .map(|date| format!("{}T00:00:00", date))
+            })
+            .ok();
+
+        let rating = review.rating.unwrap_or(0.0).round().clamp(0.0, 5.0) as u8;
+
+        let response = wp
+            .create_product_review(
+                wc_product_id,
+                review.author,
+                reviewer_email.clone(),
+                content,
+                rating,
+                date_created,
+            )
+            .await?;
+
+        if let Some(wc_review_id) = response["id"].as_u64() {
+            set_review_wc_id(db, pending.id, wc_review_id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the stored profile of a developer, scraping its page if it is missing or outdated.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `limiter` - The rate limiter of the requests sent to Addons.
+/// * `developer_url` - The URL of the developer page.
+/// * `rules` - The extractor rules.
+/// * `age_url` - The number of hours a scraped profile is kept.
+///
+/// # Returns
+///
+/// The developer and whether its profile was just scraped, `None` if the page has no profile.
+async fn developer_profile(
+    db: &Arc<Mutex<Connection>>,
+    limiter: &RateLimiter,
+    developer_url: &str,
+    rules: &ExtractorRules,
+    age_url: i64,
+) -> Result<Option<(StoredDeveloper, bool)>> {
+    if let Some(developer) = get_developer(db, developer_url).await? {
+        let date_modified = DateTime::parse_from_rfc3339(&developer.date_modified)?;
+        if (Utc::now() - date_modified.with_timezone(&Utc)).num_hours() <= age_url {
+            return Ok(Some((developer, false)));
+        }
+    }
+
+    if !load_robots_rules(db).await?.is_allowed(developer_url) {
+        println!(
+            "{}",
+            format!("URL disallowed by robots.txt: {}", developer_url).yellow()
+        );
+        return Ok(None);
+    }
+
+    println!("{}", format!("Scraping developer: {}", developer_url).cyan());
+    let (status, body) = send_url_to_flaresolverr(db, limiter, developer_url).await?;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("HTTP {}: {}", status.as_u16(), body.message));
+    }
+
+    let profile = extract_developer_profile(&Html::parse_document(&body.solution.response), rules);
+    let Some(profile) = profile else {
+        eprintln!("{}", format!("No developer profile found: {}", developer_url).red());
+        return Ok(None);
+    };
+    insert_developer_into_sql(db, developer_url, &profile).await?;
+
+    Ok(get_developer(db, developer_url)
+        .await?
+        .map(|developer| (developer, true)))
+}
+
+/// Assigns the developer brand to a product, creating the brand if needed.
+///
+/// The description of the brand is updated when the profile was scraped again,
+/// the logo is only set when the brand is created, so it isn't uploaded on each update.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `wp` - The WordPress API client.
+/// * `wc_product_id` - The WooCommerce product ID.
+/// * `developer` - The stored developer profile.
+/// * `refreshed` - Whether the profile was just scraped.
+/// * `sanitize_options` - The options used to sanitize the description.
+async fn assign_developer_brand(
+    db: &Arc<Mutex<Connection>>,
+    wp: &Auth,
+    wc_product_id: u32,
+    developer: &StoredDeveloper,
+    refreshed: bool,
+    sanitize_options: &SanitizeOptions,
+) -> Result<()> {
+    let profile = &developer.profile;
+    let mut fields = serde_json::Map::new();
+
+    let brand_id = match developer.wc_brand_id {
+        Some(brand_id) => brand_id,
+        None => {
+            let brand_id = wp.find_or_create_brand(&profile.name).await?;
+            set_developer_wc_brand_id(db, &developer.url, brand_id).await?;
+            if let Some(logo) = &profile.logo {
+                fields.insert("image".to_string(), json!({ "src": logo }));
+            }
+            brand_id
+        }
+    };
+
+    if refreshed || !fields.is_empty() {
+        if let Some(description) = &profile.description {
+            fields.insert(
+                "description".to_string(),
+                json!(sanitize_html(description, sanitize_options)),
+            );
+        }
+        if !fields.is_empty() {
+            wp.update_brand(brand_id, Value::Object(fields)).await?;
+        }
+    }
+
+    wp.update_product(wc_product_id, json!({ "brands": [brand_id] }))
+        .await?;
+    println!("{}", format!("Brand assigned: {}", profile.name).green());
+
+    Ok(())
+}
+
+/// Sends the upsells and cross-sells that changed since the last sync to WooCommerce.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `wp` - The WordPress API client.
+/// * `ps_product_id` - The PrestaShop product ID to sync, all products if `None`.
+///
+/// # Returns
+///
+/// `Ok(())` once every product is processed, failed updates are logged and retried on the next sync.
+async fn sync_relations(
+    db: &Arc<Mutex<Connection>>,
+    wp: &Auth,
+    ps_product_id: Option<u32>,
+) -> Result<()> {
+    for pending in get_pending_relations(db, ps_product_id).await? {
+        match wp.update_product(pending.wc_product_id, pending.fields()).await {
+            Ok(_) => {
+                set_relations_synced(db, &pending).await?;
+                println!(
+                    "{}",
+                    format!(
+                        "Related modules linked: {} upsells, {} cross-sells | id: {}",
+                        pending.upsell_ids.len(),
+                        pending.cross_sell_ids.len(),
+                        pending.ps_product_id
+                    )
+                        .green()
+                );
+            }
+            Err(e) => eprintln!(
+                "{}",
+                format!("Failed to link related modules: {:?}", e).red()
+            ),
+        }
+    }
 
     Ok(())
 }
 
+/// Retires the URLs missing from several sitemap refreshes once their removal is confirmed.
+///
+/// Each URL missing from at least `removal_threshold` consecutive refreshes is fetched: a 404
+/// or 410 confirms the removal, the URL is no longer scraped and the `removed_products` action
+/// is applied to the WooCommerce product of the main language. A URL still online gets its
+/// missing refreshes cleared.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+///
+/// # Returns
+///
+/// `Ok(())` once every missing URL is checked, failed checks are retried on the next run.
+pub async fn retire_removed_products(db: &Arc<Mutex<Connection>>) -> Result<()> {
+    let removal_threshold = get_configuration_value_as_i64(db, "removal_threshold").await?;
+    let missing_urls = get_missing_urls(db, removal_threshold).await?;
+    if missing_urls.is_empty() {
+        return Ok(());
+    }
+
+    let removed_products = load_removed_products(db).await?;
+    let sitemap_langs = load_sitemap_langs(db).await?;
+    let main_lang = sitemap_langs.first().cloned().unwrap_or_default();
+
+    let wordpress_url = get_configuration_value(db, "wordpress_url").await?;
+    let username_api = get_configuration_value(db, "username_api").await?;
+    let password_api = get_configuration_value(db, "password_api").await?;
+    let wp = Auth::new(wordpress_url, username_api, password_api);
+
+    // The URLs disallowed by robots.txt are left unchecked, its Crawl-delay spaces out the requests
+    let robots = load_robots_rules(db).await?;
+    let limiter = RateLimiter::new(db, robots.crawl_delay());
+
+    for missing in missing_urls {
+        if !robots.is_allowed(&missing.url) {
+            println!(
+                "{}",
+                format!("URL disallowed by robots.txt: {}", missing.url).yellow()
+            );
+            continue;
+        }
+
+        let page_status = match send_url_to_flaresolverr(db, &limiter, &missing.url).await {
+            Ok((_, body)) => body.solution.status,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Failed to check removed URL {}: {:?}", missing.url, e).red()
+                );
+                continue;
+            }
+        };
+
+        match page_status {
+            404 | 410 => {
+                println!(
+                    "{}",
+                    format!(
+                        "Product removed from Addons: {} | id: {}",
+                        missing.url,
+                        missing.ps_product_id.unwrap_or(0)
+                    )
+                        .yellow()
+                );
+
+                let main_page = missing.lang.as_deref().is_none_or(|lang| lang == main_lang);
+                if let Some(wc_product_id) = missing.wc_product_id.filter(|_| main_page) {
+                    let result = if removed_products == RemovedProducts::Delete {
+                        wp.delete_product(wc_product_id).await.map(|_| ())
+                    } else if let Some(fields) = removed_products.update_fields() {
+                        wp.update_product(wc_product_id, fields).await.map(|_| ())
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(e) = result {
+                        eprintln!(
+                            "{}",
+                            format!("Failed to retire product {}: {:?}", wc_product_id, e).red()
+                        );
+                        continue;
+                    }
+                }
+
+                set_url_retired(db, &missing.url).await?;
+            }
+            200 => reset_missing_count(db, &missing.url).await?,
+            _ => {}
+        }
+
+        // Generate random delay
+        generate_random_delay(1000, 8000).await;
+    }
+
+    Ok(())
+}
+
+/// Returns the attribute listing the supported PrestaShop major lines, creating its terms if needed.
+///
+/// The attribute is named after the `ps_version_attribute` configuration, nothing
+/// is returned if it isn't set. Errors are logged, the product is then created without it.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `wp` - The WordPress API client.
+/// * `range` - The supported PrestaShop versions.
+async fn ps_version_attributes(
+    db: &Arc<Mutex<Connection>>,
+    wp: &Auth,
+    range: &PsVersionRange,
+) -> Vec<ProductAttribute> {
+    let attribute_name = match get_configuration_value(db, "ps_version_attribute").await {
+        Ok(name) if !name.is_empty() => name,
+        _ => return Vec::new(),
+    };
+    let lines = range.major_lines();
+    if lines.is_empty() {
+        return Vec::new();
+    }
+
+    let attribute = async {
+        let attribute_id = wp.find_or_create_attribute(&attribute_name).await?;
+        for line in &lines {
+            wp.find_or_create_attribute_term(attribute_id, line).await?;
+        }
+        Ok::<_, anyhow::Error>(ProductAttribute {
+            id: attribute_id,
+            options: lines.iter().map(|line| line.to_string()).collect(),
+        })
+    };
+
+    match attribute.await {
+        Ok(attribute) => vec![attribute],
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Failed to set PrestaShop versions attribute: {:?}", e).red()
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Converts a scraped price to the shop currency and applies the pricing rules.
+///
+/// If the price can't be converted, it is left unknown and the product is created without price.
+fn compute_shop_price(
+    price_ht: &str,
+    currency: Option<&str>,
+    shop_currency: &str,
+    exchange_rates: Option<&ExchangeRates>,
+    pricing_rules: &PricingRules,
+) -> ComputedPrice {
+    let price_ht = match convert_price(price_ht, currency, shop_currency, exchange_rates) {
+        Ok(price_ht) => price_ht,
+        Err(e) => {
+            eprintln!("{}", format!("Failed to convert price: {:?}", e).red());
+            String::new()
+        }
+    };
+
+    compute_price(&price_ht, pricing_rules)
+}
+
 /// Sends a URL to FlareSolverr for scraping.
 ///
 /// # Arguments
 ///
 /// * `db` - A reference to the database connection.
+/// * `limiter` - The rate limiter the request waits for.
 /// * `url` - The URL to be scraped.
 ///
 /// # Returns
@@ -351,8 +1317,12 @@ async fn process_url(db: &Arc<Mutex<Connection>>, url: String) -> Result<()> {
 /// If successful, returns the status and body of the response.
 async fn send_url_to_flaresolverr(
     db: &Arc<Mutex<Connection>>,
+    limiter: &RateLimiter,
     url: &str,
 ) -> Result<(reqwest::StatusCode, extract_data::FlareSolverrResponse)> {
+    // Wait for the Crawl-delay since the previous request, of any worker
+    limiter.wait().await?;
+
     // Create an HTTP client
     let client = Client::new();
 
@@ -364,7 +1334,7 @@ async fn send_url_to_flaresolverr(
     });
 
     // Get the FlareSolverr URL from the configuration
-    let flaresolverr_url = get_configuration_value(db, "flaresolverr_url").await?;
+    let flaresolverr_url = get_flaresolverr_url(db).await?;
 
     // Send the request to FlareSolverr
     let response = client
@@ -387,6 +1357,8 @@ async fn send_url_to_flaresolverr(
 
 /// Updates the `date_modified` and `http_code` fields for a given URL in the database.
 ///
+/// The consecutive failures are counted to delay the next retry, a success clears them.
+///
 /// # Arguments
 ///
 /// * `db` - A shared, locked database connection.
@@ -405,8 +1377,29 @@ async fn update_url_in_database(
 ) -> Result<()> {
     let db = db.lock().await;
     db.execute(
-        "UPDATE urls SET date_modified = ?1, http_code = ?2 WHERE url = ?3",
+        "UPDATE urls SET date_modified = ?1, http_code = ?2,
+        fail_count = CASE WHEN ?2 = 200 THEN 0 ELSE fail_count + 1 END WHERE url = ?3",
         params![date_modified, http_code, url],
     )?;
     Ok(())
 }
+
+/// Returns the language of the sitemap a URL comes from.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `url` - The URL to look up.
+///
+/// # Returns
+///
+/// The language code, or `None` if the URL was stored before languages were tracked.
+async fn get_url_language(db: &Arc<Mutex<Connection>>, url: &str) -> Result<Option<String>> {
+    let db = db.lock().await;
+    let lang = db
+        .query_row("SELECT lang FROM urls WHERE url = ?1", params![url], |row| {
+            row.get::<_, Option<String>>(0)
+        })
+        .optional()?;
+    Ok(lang.flatten())
+}
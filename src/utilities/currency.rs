@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use rusqlite::Connection;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::get_configuration_value;
+
+/// Exchange rates against a base currency, as described in the rates file.
+///
+/// ```toml
+/// base = "EUR"
+///
+/// [rates]
+/// USD = 1.08 # 1 EUR = 1.08 USD
+/// GBP = 0.85
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExchangeRates {
+    pub base: String,
+    pub rates: HashMap<String, f64>,
+}
+
+impl ExchangeRates {
+    /// Parses the rates from TOML content.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let mut rates: ExchangeRates = toml::from_str(content)?;
+
+        rates.base = rates.base.to_uppercase();
+        rates.rates = rates
+            .rates
+            .into_iter()
+            .map(|(currency, rate)| (currency.to_uppercase(), rate))
+            .collect();
+
+        if let Some((currency, _)) = rates.rates.iter().find(|(_, rate)| **rate <= 0.0) {
+            return Err(anyhow!("Invalid exchange rate for {}", currency));
+        }
+
+        Ok(rates)
+    }
+
+    /// Converts an amount between two currencies, `None` if a rate is missing.
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        if from.eq_ignore_ascii_case(to) {
+            return Some(amount);
+        }
+        Some(amount / self.rate(from)? * self.rate(to)?)
+    }
+
+    /// Returns the amount of the currency for one unit of the base currency.
+    fn rate(&self, currency: &str) -> Option<f64> {
+        let currency = currency.to_uppercase();
+        if currency == self.base {
+            Some(1.0)
+        } else {
+            self.rates.get(&currency).copied()
+        }
+    }
+}
+
+/// Loads the exchange rates from the file set in `rates_file`, `None` if it isn't set.
+///
+/// A relative path is resolved from the directory containing the executable.
+pub async fn load_exchange_rates(db: &Arc<Mutex<Connection>>) -> Result<Option<ExchangeRates>> {
+    let rates_file = get_configuration_value(db, "rates_file").await?;
+
+    if rates_file.is_empty() {
+        return Ok(None);
+    }
+
+    let mut rates_path = PathBuf::from(&rates_file);
+    if rates_path.is_relative() {
+        let mut current_dir =
+            env::current_exe().context("Failed to get current executable path")?;
+        current_dir.pop();
+        rates_path = current_dir.join(rates_path);
+    }
+
+    let content = fs::read_to_string(&rates_path)
+        .context(format!("Failed to read rates file: {:?}", rates_path))?;
+
+    ExchangeRates::from_toml(&content)
+        .map(Some)
+        .context(format!("Failed to parse rates file: {:?}", rates_path))
+}
+
+/// Converts a scraped price to the shop currency.
+///
+/// The price is returned unchanged if it's empty, if its currency or the shop currency
+/// is unknown, or if they are the same. A decimal comma is read as a decimal point. Fails
+/// if the price isn't a number or if the rates don't allow the conversion.
+pub fn convert_price(
+    price: &str,
+    currency: Option<&str>,
+    shop_currency: &str,
+    rates: Option<&ExchangeRates>,
+) -> Result<String> {
+    let Some(currency) = currency else {
+        return Ok(price.to_string());
+    };
+    if shop_currency.is_empty() || currency.eq_ignore_ascii_case(shop_currency) {
+        return Ok(price.to_string());
+    }
+    if price.trim().is_empty() {
+        return Ok(price.to_string());
+    }
+    let amount = price
+        .trim()
+        .replace(',', ".")
+        .parse::<f64>()
+        .context(format!("Invalid price in {}: {}", currency, price))?;
+
+    let rates = rates.context(format!(
+        "Price in {} but the shop currency is {} and no rates_file is set",
+        currency, shop_currency
+    ))?;
+    let converted = rates
+        .convert(amount, currency, shop_currency)
+        .context(format!(
+            "No exchange rate to convert {} to {}",
+            currency, shop_currency
+        ))?;
+
+    Ok(format!("{:.2}", converted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> ExchangeRates {
+        ExchangeRates::from_toml("base = \"eur\"\n[rates]\nUSD = 1.25\ngbp = 0.8\n").unwrap()
+    }
+
+    #[test]
+    fn converts_through_the_base_currency() {
+        let rates = rates();
+
+        assert_eq!(rates.convert(10.0, "EUR", "USD"), Some(12.5));
+        assert_eq!(rates.convert(12.5, "usd", "EUR"), Some(10.0));
+        assert_eq!(rates.convert(12.5, "USD", "GBP"), Some(8.0));
+        assert_eq!(rates.convert(10.0, "CHF", "EUR"), None);
+    }
+
+    #[test]
+    fn converts_prices_to_the_shop_currency() {
+        let rates = rates();
+
+        assert_eq!(
+            convert_price("99.00", Some("USD"), "EUR", Some(&rates)).unwrap(),
+            "79.20"
+        );
+        assert_eq!(
+            convert_price("79.99", Some("EUR"), "EUR", None).unwrap(),
+            "79.99"
+        );
+        assert_eq!(convert_price("79.99", None, "EUR", None).unwrap(), "79.99");
+        assert_eq!(
+            convert_price("99,00", Some("USD"), "EUR", Some(&rates)).unwrap(),
+            "79.20"
+        );
+        assert_eq!(convert_price("", Some("USD"), "EUR", None).unwrap(), "");
+        assert!(convert_price("99 USD", Some("USD"), "EUR", Some(&rates)).is_err());
+        assert!(convert_price("79.99", Some("USD"), "EUR", None).is_err());
+        assert!(convert_price("79.99", Some("CHF"), "EUR", Some(&rates)).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_rates() {
+        assert!(ExchangeRates::from_toml("base = \"EUR\"\n[rates]\nUSD = 0\n").is_err());
+    }
+}
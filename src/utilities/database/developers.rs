@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use tokio::sync::Mutex;
+
+use crate::extractors::ps_addons::extract_developer_profile::DeveloperProfile;
+
+/// A developer profile stored in the database.
+pub struct StoredDeveloper {
+    pub url: String,
+    pub profile: DeveloperProfile,
+    /// ID of the WooCommerce brand of the developer, once created.
+    pub wc_brand_id: Option<u32>,
+    pub date_modified: String,
+}
+
+/// A developer of the catalogue, with the number of its scraped products.
+pub struct DeveloperSummary {
+    pub url: String,
+    pub name: String,
+    pub country: Option<String>,
+    pub badges: Vec<String>,
+    pub module_count: Option<u32>,
+    pub rating_value: Option<f64>,
+    pub scraped_products: u32,
+}
+
+/// Stores the profile of a developer, keeping its WooCommerce brand ID.
+pub async fn insert_developer_into_sql(
+    db: &Arc<Mutex<Connection>>,
+    url: &str,
+    profile: &DeveloperProfile,
+) -> Result<()> {
+    let db = db.lock().await;
+    let date_modified = Utc::now().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO developers (url, name, description, logo, country, badges, module_count,
+        rating_value, date_modified)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        ON CONFLICT(url) DO UPDATE SET name = excluded.name, description = excluded.description,
+        logo = excluded.logo, country = excluded.country, badges = excluded.badges,
+        module_count = excluded.module_count, rating_value = excluded.rating_value,
+        date_modified = excluded.date_modified",
+        params![
+            url,
+            profile.name,
+            profile.description,
+            profile.logo,
+            profile.country,
+            serde_json::to_string(&profile.badges)?,
+            profile.module_count,
+            profile.rating_value,
+            date_modified
+        ],
+    )
+    .context("Failed to insert or update developer in the database")?;
+
+    Ok(())
+}
+
+/// Returns the stored profile of the developer page, if it was scraped.
+pub async fn get_developer(
+    db: &Arc<Mutex<Connection>>,
+    url: &str,
+) -> Result<Option<StoredDeveloper>> {
+    let db = db.lock().await;
+
+    db.query_row(
+        "SELECT url, name, description, logo, country, badges, module_count, rating_value,
+        wc_brand_id, date_modified FROM developers WHERE url = ?1",
+        params![url],
+        |row| {
+            Ok(StoredDeveloper {
+                url: row.get(0)?,
+                profile: DeveloperProfile {
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    logo: row.get(3)?,
+                    country: row.get(4)?,
+                    badges: parse_badges(row.get(5)?),
+                    module_count: row.get(6)?,
+                    rating_value: row.get(7)?,
+                },
+                wc_brand_id: row.get(8)?,
+                date_modified: row.get(9)?,
+            })
+        },
+    )
+    .optional()
+    .context("Failed to read developer from the database")
+}
+
+/// Stores the ID of the WooCommerce brand of a developer.
+pub async fn set_developer_wc_brand_id(
+    db: &Arc<Mutex<Connection>>,
+    url: &str,
+    wc_brand_id: u32,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE developers SET wc_brand_id = ?2 WHERE url = ?1",
+        params![url, wc_brand_id],
+    )
+    .context("Failed to update developer brand in the database")?;
+
+    Ok(())
+}
+
+/// Returns the scraped developers, with the number of their products in the database.
+pub async fn get_developers(db: &Arc<Mutex<Connection>>) -> Result<Vec<DeveloperSummary>> {
+    let db = db.lock().await;
+    let mut stmt = db.prepare(
+        "SELECT d.url, d.name, d.country, d.badges, d.module_count, d.rating_value,
+        COUNT(p.ps_product_id)
+        FROM developers d LEFT JOIN products p ON p.developer_url = d.url
+        GROUP BY d.url ORDER BY d.name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(DeveloperSummary {
+            url: row.get(0)?,
+            name: row.get(1)?,
+            country: row.get(2)?,
+            badges: parse_badges(row.get(3)?),
+            module_count: row.get(4)?,
+            rating_value: row.get(5)?,
+            scraped_products: row.get(6)?,
+        })
+    })?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .context("Failed to read developers from the database")
+}
+
+fn parse_badges(badges: String) -> Vec<String> {
+    serde_json::from_str(&badges).unwrap_or_default()
+}
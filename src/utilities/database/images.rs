@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use tokio::sync::Mutex;
+
+/// Returns the WordPress URL of an image already uploaded from a source, `None` if it wasn't.
+pub async fn get_rehosted_image(
+    db: &Arc<Mutex<Connection>>,
+    source: &str,
+) -> Result<Option<String>> {
+    let db = db.lock().await;
+
+    db.query_row(
+        "SELECT url FROM rehosted_images WHERE source = ?1",
+        params![source],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to read the rehosted image from the database")
+}
+
+/// Stores the WordPress URL of an image uploaded from a source.
+pub async fn set_rehosted_image(
+    db: &Arc<Mutex<Connection>>,
+    source: &str,
+    url: &str,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "INSERT INTO rehosted_images (source, url, date_uploaded) VALUES (?1, ?2, ?3)
+        ON CONFLICT(source) DO UPDATE SET url = excluded.url,
+        date_uploaded = excluded.date_uploaded",
+        params![source, url, Utc::now().to_rfc3339()],
+    )
+    .context("Failed to insert or update the rehosted image in the database")?;
+
+    Ok(())
+}
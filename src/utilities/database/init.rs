@@ -1,8 +1,11 @@
 use std::env;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
-use rusqlite::Connection;
+use rusqlite::{params, Connection, TransactionBehavior};
+
+use crate::utilities::database::reviews::review_text_hash;
 use tokio::sync::Mutex;
 
 #[derive(Clone)]
@@ -23,8 +26,13 @@ impl Database {
 /// 1. Retrieves the current executable path.
 /// 2. Navigates to the directory containing the executable.
 /// 3. Checks if the SQLite file `urls.sqlite` exists.
-/// 4. Opens a connection to the SQLite database.
+/// 4. Opens a connection to the SQLite database, in WAL mode so that several workers can share it.
 /// 5. Creates the "urls" table if the database file didn't exist before.
+/// 6. Creates the "products", "reviews", "videos", "prices" and other tables if they don't exist yet,
+///    and adds the columns missing from the tables of an older database.
+///
+/// Steps 5 and 6 run in a single write transaction, so that several workers started together
+/// don't change the schema twice.
 ///
 /// # Errors
 ///
@@ -50,12 +58,24 @@ pub async fn init() -> anyhow::Result<Database> {
 
     // Open a connection to the SQLite database
     let conn = Connection::open(&db_path).context("Failed to open SQLite database")?;
+
+    // Several workers may share the database: readers don't block the writer and a locked
+    // database is waited for instead of failing
+    conn.busy_timeout(Duration::from_secs(30))
+        .context("Failed to set the SQLite busy timeout")?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+        .context("Failed to enable the SQLite WAL journal")?;
     let db = Arc::new(Mutex::new(conn));
 
     // Create the "urls" table if the database file didn't exist before
     {
-        let db = db.lock().await;
-        // Create the "urls" table if the database file didn't exist before
+        let mut conn = db.lock().await;
+        // Workers started together would race on the schema changes: they run in a single write
+        // transaction, and the other workers wait for it before finding the schema up to date
+        let db = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .context("Failed to lock the database for its schema update")?;
+
         if !db_exists {
             db.execute(
                 "CREATE TABLE IF NOT EXISTS urls (
@@ -80,7 +100,301 @@ pub async fn init() -> anyhow::Result<Database> {
             )
             .context("Failed to create configuration table")?;
         }
+
+        // Tables added after the first release, also created on existing databases
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS products (
+                ps_product_id INTEGER PRIMARY KEY,
+                rating_value REAL,
+                review_count INTEGER,
+                date_modified TEXT
+            )",
+            [],
+        )
+        .context("Failed to create products table")?;
+
+        // Columns added to the "urls" table after its creation. The product id links the
+        // translations. The alternates and images come from the sitemap extensions. The sitemap
+        // presence columns detect the removed products. The failures delay the retries. The lease
+        // reserves a URL to a worker. The URLs disallowed by robots.txt are flagged.
+        for (column, definition) in [
+            ("lang", "TEXT"),
+            ("ps_product_id", "INTEGER"),
+            ("alternates", "TEXT"),
+            ("images", "TEXT"),
+            ("sitemap_url", "TEXT"),
+            ("last_seen_in_sitemap", "TEXT"),
+            ("missing_count", "INTEGER NOT NULL DEFAULT 0"),
+            ("date_retired", "TEXT"),
+            ("fail_count", "INTEGER NOT NULL DEFAULT 0"),
+            ("lease_owner", "TEXT"),
+            ("lease_expires", "TEXT"),
+            ("robots_disallowed", "INTEGER NOT NULL DEFAULT 0"),
+        ] {
+            add_column_if_missing(&db, "urls", column, definition)?;
+        }
+
+        // Columns added to the "products" table after its creation
+        for (column, definition) in [
+            ("title", "TEXT"),
+            ("ps_url", "TEXT"),
+            ("ps_version_required", "TEXT"),
+            ("ps_version_min", "TEXT"),
+            ("ps_version_max", "TEXT"),
+            ("ps_version_min_key", "INTEGER"),
+            ("ps_version_max_key", "INTEGER"),
+            ("price_ht", "REAL"),
+            ("price_computed", "REAL"),
+            ("wc_product_id", "INTEGER"),
+            ("wc_relations", "TEXT"),
+            ("developer_url", "TEXT"),
+            ("kind", "TEXT"),
+            ("developer_name", "TEXT"),
+            ("module_version", "TEXT"),
+            ("last_update", "TEXT"),
+            ("multistore_compatibility", "TEXT"),
+            ("publication_date", "TEXT"),
+            ("with_override", "TEXT"),
+            ("brand", "TEXT"),
+            ("availability", "TEXT"),
+            ("demo_url", "TEXT"),
+        ] {
+            add_column_if_missing(&db, "products", column, definition)?;
+        }
+
+        // Data migrations run once, the last one applied is kept in the user_version of the file
+        let schema_version: i64 = db
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .context("Failed to read the database version")?;
+        if schema_version < 1 {
+            // The minimum versions that couldn't be parsed were stored as 0, matching every version
+            db.execute(
+                "UPDATE products SET ps_version_min_key = NULL WHERE ps_version_min_key = 0",
+                [],
+            )
+            .context("Failed to clear the invalid minimum versions")?;
+            db.pragma_update(None, "user_version", 1)
+                .context("Failed to update the database version")?;
+        }
+
+        db.execute(REVIEWS_TABLE, [])
+            .context("Failed to create reviews table")?;
+
+        // Reviews were first identified by product, author and date only, which merged the
+        // reviews of an author on the same day
+        if !has_column(&db, "reviews", "text_hash")? {
+            migrate_reviews(&db)?;
+        }
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS videos (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                ps_product_id INTEGER NOT NULL,
+                provider TEXT NOT NULL,
+                video_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                embed_url TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                UNIQUE(ps_product_id, provider, video_id)
+            )",
+            [],
+        )
+        .context("Failed to create videos table")?;
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS prices (
+                ps_product_id INTEGER NOT NULL,
+                currency TEXT NOT NULL,
+                amount REAL NOT NULL,
+                date_modified TEXT,
+                PRIMARY KEY(ps_product_id, currency)
+            )",
+            [],
+        )
+        .context("Failed to create prices table")?;
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS product_relations (
+                ps_product_id INTEGER NOT NULL,
+                related_ps_product_id INTEGER NOT NULL,
+                relation TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                PRIMARY KEY(ps_product_id, relation, related_ps_product_id)
+            )",
+            [],
+        )
+        .context("Failed to create product_relations table")?;
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS developers (
+                url TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                logo TEXT,
+                country TEXT,
+                badges TEXT NOT NULL,
+                module_count INTEGER,
+                rating_value REAL,
+                wc_brand_id INTEGER,
+                date_modified TEXT NOT NULL
+            )",
+            [],
+        )
+        .context("Failed to create developers table")?;
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS translations (
+                ps_product_id INTEGER NOT NULL,
+                lang TEXT NOT NULL,
+                url TEXT NOT NULL,
+                title TEXT NOT NULL,
+                description TEXT NOT NULL,
+                features TEXT NOT NULL,
+                date_modified TEXT,
+                PRIMARY KEY(ps_product_id, lang)
+            )",
+            [],
+        )
+        .context("Failed to create translations table")?;
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS sitemaps (
+                url TEXT PRIMARY KEY,
+                lang TEXT NOT NULL,
+                last_mod TEXT,
+                date_ingested TEXT NOT NULL
+            )",
+            [],
+        )
+        .context("Failed to create sitemaps table")?;
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS robots (
+                url TEXT PRIMARY KEY,
+                content TEXT NOT NULL,
+                date_fetched TEXT NOT NULL
+            )",
+            [],
+        )
+        .context("Failed to create robots table")?;
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS rehosted_images (
+                source TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                date_uploaded TEXT NOT NULL
+            )",
+            [],
+        )
+        .context("Failed to create rehosted_images table")?;
+
+        db.execute(JOBS_TABLE, [])
+            .context("Failed to create jobs table")?;
+
+        db.execute(RATE_LIMITS_TABLE, [])
+            .context("Failed to create rate_limits table")?;
+
+        db.commit()
+            .context("Failed to commit the database schema update")?;
     }
 
     Ok(Database::new(db))
 }
+
+/// The reviews of a product, identified by author, date and a hash of their text.
+pub(crate) const REVIEWS_TABLE: &str = "CREATE TABLE IF NOT EXISTS reviews (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    ps_product_id INTEGER NOT NULL,
+    author TEXT NOT NULL,
+    date TEXT NOT NULL,
+    rating REAL,
+    text TEXT NOT NULL,
+    text_hash TEXT NOT NULL,
+    developer_reply TEXT,
+    wc_review_id INTEGER,
+    UNIQUE(ps_product_id, author, date, text_hash)
+)";
+
+/// The jobs run by a single worker at a time, with the lease of the worker running them.
+pub(crate) const JOBS_TABLE: &str = "CREATE TABLE IF NOT EXISTS jobs (
+    name TEXT PRIMARY KEY,
+    lease_owner TEXT,
+    lease_expires TEXT,
+    date_done TEXT
+)";
+
+/// The next request slot of each rate limit shared by the workers, in milliseconds.
+pub(crate) const RATE_LIMITS_TABLE: &str = "CREATE TABLE IF NOT EXISTS rate_limits (
+    name TEXT PRIMARY KEY,
+    next_slot INTEGER NOT NULL
+)";
+
+/// Rebuilds the "reviews" table of an older database with the text hash in its key.
+///
+/// Runs inside the schema update transaction of [`init`].
+fn migrate_reviews(tx: &Connection) -> anyhow::Result<()> {
+    tx.execute("ALTER TABLE reviews RENAME TO reviews_old", [])?;
+    tx.execute(REVIEWS_TABLE, [])?;
+    {
+        let mut select = tx.prepare(
+            "SELECT id, ps_product_id, author, date, rating, text, developer_reply, wc_review_id
+            FROM reviews_old",
+        )?;
+        let mut insert = tx.prepare(
+            "INSERT INTO reviews (id, ps_product_id, author, date, rating, text, text_hash,
+            developer_reply, wc_review_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        let mut rows = select.query([])?;
+        while let Some(row) = rows.next()? {
+            let text: String = row.get(5)?;
+            insert.execute(params![
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<f64>>(4)?,
+                text,
+                review_text_hash(&text),
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<i64>>(7)?
+            ])?;
+        }
+    }
+    tx.execute("DROP TABLE reviews_old", [])
+        .context("Failed to migrate reviews table")?;
+
+    Ok(())
+}
+
+/// Checks whether a table has a column.
+fn has_column(db: &Connection, table: &str, column: &str) -> anyhow::Result<bool> {
+    let mut stmt = db.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+
+    Ok(exists)
+}
+
+/// Adds a column to an existing table if it doesn't have it yet.
+fn add_column_if_missing(
+    db: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> anyhow::Result<()> {
+    if !has_column(db, table, column)? {
+        db.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )
+        .context(format!(
+            "Failed to add column {} to {} table",
+            column, table
+        ))?;
+    }
+
+    Ok(())
+}
@@ -1,123 +1,243 @@
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, FixedOffset, Utc};
-use quick_xml::events::Event;
-use quick_xml::Reader;
-use regex::Regex;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
 use rusqlite::{params, Connection};
+use serde_json::{json, Map, Value};
 use tokio::sync::Mutex;
+use tokio::task;
 
-pub async fn insert_sitemap_into_sql(db: &Arc<Mutex<Connection>>, xml_content: &str) -> Result<()> {
-    let db = db.lock().await;
-
-    let mut reader = Reader::from_str(xml_content);
-    reader.config_mut().trim_text(true);
-
-    let mut buf = Vec::new();
-    let mut url = String::new();
-    let mut last_mod = String::new();
-    let mut change_freq = String::new();
-
-    let base_url = "https://addons.prestashop.com";
-    let content_url = "/content/";
-    let product_url = Regex::new(r"^\d+-").unwrap();
-
-    let mut skip = false;
-
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => match e.name().as_ref() {
-                b"loc" => {
-                    url = reader
-                        .read_text(e.name())
-                        .context("Failed to read loc text")?
-                        .parse()?;
-
-                    // Skip non-product URLs
-                    if url.contains(base_url) && url.contains(content_url) {
-                        eprintln!("Skipping URL: {}", url);
-                        skip = true;
-                    } else if url.starts_with(base_url) {
-                        // Remove the base URL and split the remaining part
-                        let path = &url[base_url.len()..];
-                        let parts: Vec<&str> = path.split('/').collect();
-
-                        // Check if the path follows the pattern of a product URL
-                        if parts.len() >= 4 {
-                            let id_and_name = parts[3];
-                            // Check if it follows the pattern of product URL
-                            if product_url.is_match(id_and_name) && id_and_name.ends_with(".html") {
-                                skip = false;
-                            } else {
-                                eprintln!("Skipping URL: {}", url);
-                                skip = true;
-                            }
-                        } else {
-                            eprintln!("Skipping URL: {}", url);
-                            skip = true;
-                        }
-                    } else {
-                        eprintln!("Skipping URL: {}", url);
-                        skip = true;
-                    }
-                }
-                b"lastmod" => {
-                    last_mod = reader
-                        .read_text(e.name())
-                        .context("Failed to read lastmod text")?
-                        .parse()?;
-
-                    // Verify the date format
-                    if last_mod.parse::<DateTime<FixedOffset>>().is_err() {
-                        eprintln!("Invalid date format: {}", last_mod);
-                        skip = true;
-                    }
-                }
-                b"changefreq" => {
-                    change_freq = reader
-                        .read_text(e.name())
-                        .context("Failed to read changefreq text")?
-                        .parse()?;
-                }
-                _ => (),
-            },
-            Ok(Event::End(ref e)) => {
-                if e.name().as_ref() == b"url" {
-                    // Skip the insertion if marked to skip
-                    if skip {
-                        continue;
-                    }
-
-                    // Insert or update the data
-                    db.execute(
-                        "INSERT INTO urls (url, last_mod, change_freq) VALUES (?1, ?2, ?3)
-                        ON CONFLICT(url) DO UPDATE SET last_mod = excluded.last_mod, change_freq = excluded.change_freq",
-                        params![url, last_mod, change_freq],
-                    ).context("Failed to insert or update data in the database")?;
-                }
-            }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(anyhow::anyhow!(
-                    "Error at position {}: {:?}",
-                    reader.buffer_position(),
-                    e
-                ))
+use crate::utilities::extract_id_from_url::extract_id_from_url;
+use crate::utilities::product_kind::classify_url;
+use crate::utilities::robots::RobotsRules;
+use crate::utilities::sitemap::sitemap_xml::SitemapUrl;
+
+/// Stores the product URLs of a sitemap with their language.
+///
+/// The PrestaShop product id of each URL is stored too, it links the pages of a
+/// product in the different languages. The hreflang alternates and images of each
+/// URL are stored as JSON. Each URL is marked as seen in `sitemap_url`, which clears
+/// its missing refreshes and its retirement. The URLs disallowed by robots.txt are
+/// stored as such, they are not due. The URLs are stored in one transaction, on a
+/// blocking thread.
+pub async fn insert_sitemap_into_sql(
+    db: &Arc<Mutex<Connection>>,
+    sitemap_url: &str,
+    pages: Vec<SitemapUrl>,
+    lang: &str,
+    robots: &RobotsRules,
+) -> Result<()> {
+    let db = Arc::clone(db);
+    let sitemap_url = sitemap_url.to_string();
+    let lang = lang.to_string();
+    let robots = robots.clone();
+
+    task::spawn_blocking(move || {
+        let mut db = db.blocking_lock();
+        insert_pages(&mut db, &sitemap_url, pages, &lang, &robots)
+    })
+    .await
+    .context("Failed to run the sitemap insertion")?
+}
+
+fn insert_pages(
+    db: &mut Connection,
+    sitemap_url: &str,
+    pages: Vec<SitemapUrl>,
+    lang: &str,
+    robots: &RobotsRules,
+) -> Result<()> {
+    let last_seen = Utc::now().to_rfc3339();
+    let transaction = db.transaction()?;
+
+    {
+        let mut statement = transaction.prepare(
+            "INSERT INTO urls (url, last_mod, change_freq, lang, ps_product_id, alternates, images,
+            sitemap_url, last_seen_in_sitemap, missing_count, robots_disallowed)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 0, ?10)
+            ON CONFLICT(url) DO UPDATE SET last_mod = excluded.last_mod,
+            change_freq = excluded.change_freq, lang = excluded.lang,
+            ps_product_id = excluded.ps_product_id, alternates = excluded.alternates,
+            images = excluded.images, sitemap_url = excluded.sitemap_url,
+            last_seen_in_sitemap = excluded.last_seen_in_sitemap, missing_count = 0,
+            date_retired = NULL, robots_disallowed = excluded.robots_disallowed",
+        )?;
+
+        for page in pages {
+            let url = page.loc;
+
+            // Skip non-product URLs, modules and themes are kept
+            if classify_url(&url).is_none() {
+                eprintln!("Skipping URL: {}", url);
+                continue;
             }
-            _ => (),
+
+            // Verify the date format, a W3C date without time is valid too, the URL is still
+            // stored without an invalid lastmod
+            let last_mod = page.last_mod.filter(|last_mod| {
+                let valid = last_mod.parse::<DateTime<FixedOffset>>().is_ok()
+                    || NaiveDate::parse_from_str(last_mod, "%Y-%m-%d").is_ok();
+                if !valid {
+                    eprintln!("Invalid date format: {}", last_mod);
+                }
+                valid
+            });
+
+            let alternates: Map<String, Value> = page
+                .alternates
+                .into_iter()
+                .map(|(hreflang, href)| (hreflang, json!(href)))
+                .collect();
+
+            // Insert or update the data
+            statement
+                .execute(params![
+                    url,
+                    last_mod,
+                    page.change_freq,
+                    lang,
+                    extract_id_from_url(&url),
+                    Value::Object(alternates).to_string(),
+                    serde_json::to_string(&page.images)?,
+                    sitemap_url,
+                    last_seen,
+                    !robots.is_allowed(&url)
+                ])
+                .context("Failed to insert or update data in the database")?;
         }
-        buf.clear();
     }
 
-    // Insert the current date and time into the configuration table
-    let current_date = Utc::now().to_rfc3339();
-    db.execute(
-        "INSERT INTO configuration (key, value) VALUES ('last_sitemap_insert_date', ?1)
-        ON CONFLICT(key) DO UPDATE SET value = excluded.value",
-        params![current_date],
-    )
-    .context("Failed to insert or update last_xml_insert_date in the configuration table")?;
+    transaction
+        .commit()
+        .context("Failed to commit the sitemap URLs")?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::robots::Robots;
+
+    const SITEMAP_URL: &str = "https://addons.prestashop.com/sitemap_fr_1.xml";
+
+    fn urls_db() -> Connection {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE urls (url TEXT PRIMARY KEY, last_mod TEXT, change_freq TEXT, lang TEXT,
+            ps_product_id INTEGER, alternates TEXT, images TEXT, sitemap_url TEXT,
+            last_seen_in_sitemap TEXT, missing_count INTEGER NOT NULL DEFAULT 0,
+            date_retired TEXT, robots_disallowed INTEGER NOT NULL DEFAULT 0);",
+        )
+        .unwrap();
+        db
+    }
+
+    fn page(loc: &str, last_mod: &str) -> SitemapUrl {
+        SitemapUrl {
+            loc: loc.to_string(),
+            last_mod: Some(last_mod.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stores_the_listed_urls_again() {
+        let mut db = urls_db();
+        db.execute(
+            "INSERT INTO urls (url, missing_count, date_retired) VALUES
+            ('https://addons.prestashop.com/fr/seo/8734-seo-expert.html', 3,
+            '2024-05-02T10:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+
+        let pages = vec![
+            page(
+                "https://addons.prestashop.com/fr/seo/8734-seo-expert.html",
+                "2024-06-01T10:00:00+02:00",
+            ),
+            page(
+                "https://addons.prestashop.com/fr/seo/9120-sitemap.html",
+                "yesterday",
+            ),
+            page(
+                "https://addons.prestashop.com/fr/seo/9305-rich-snippets.html",
+                "2024-05-01",
+            ),
+        ];
+        insert_pages(&mut db, SITEMAP_URL, pages, "fr", &RobotsRules::default()).unwrap();
+
+        let rows: Vec<(String, Option<String>, i64, Option<String>)> = db
+            .prepare("SELECT url, last_mod, missing_count, date_retired FROM urls ORDER BY url")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "https://addons.prestashop.com/fr/seo/8734-seo-expert.html".to_string(),
+                    Some("2024-06-01T10:00:00+02:00".to_string()),
+                    0,
+                    None
+                ),
+                (
+                    "https://addons.prestashop.com/fr/seo/9120-sitemap.html".to_string(),
+                    None,
+                    0,
+                    None
+                ),
+                (
+                    "https://addons.prestashop.com/fr/seo/9305-rich-snippets.html".to_string(),
+                    Some("2024-05-01".to_string()),
+                    0,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_the_urls_disallowed_by_robots() {
+        let mut db = urls_db();
+        let robots = Robots::parse("User-agent: *\nDisallow: /fr/seo/9120-").rules_for("crawler");
+
+        let pages = vec![
+            page(
+                "https://addons.prestashop.com/fr/seo/8734-seo-expert.html",
+                "2024-06-01T10:00:00+02:00",
+            ),
+            page(
+                "https://addons.prestashop.com/fr/seo/9120-sitemap.html",
+                "2024-06-01T10:00:00+02:00",
+            ),
+        ];
+        insert_pages(&mut db, SITEMAP_URL, pages, "fr", &robots).unwrap();
+
+        let disallowed: Vec<(String, bool)> = db
+            .prepare("SELECT url, robots_disallowed FROM urls ORDER BY url")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            disallowed,
+            vec![
+                (
+                    "https://addons.prestashop.com/fr/seo/8734-seo-expert.html".to_string(),
+                    false
+                ),
+                (
+                    "https://addons.prestashop.com/fr/seo/9120-sitemap.html".to_string(),
+                    true
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use tokio::sync::Mutex;
+
+use crate::utilities::database::schedule::Lease;
+
+/// Claims a job for the worker, like the URLs: `true` if no other worker holds its lease.
+///
+/// The claim is a single upsert, so two workers never hold the same job. The lease of a
+/// worker that stopped is claimed again once it expires.
+pub async fn claim_job(db: &Arc<Mutex<Connection>>, name: &str, lease: &Lease) -> Result<bool> {
+    let db = db.lock().await;
+
+    let claimed = db
+        .query_row(
+            "INSERT INTO jobs (name, lease_owner, lease_expires) VALUES (?1, ?2, ?3)
+            ON CONFLICT(name) DO UPDATE SET lease_owner = excluded.lease_owner,
+            lease_expires = excluded.lease_expires
+            WHERE jobs.lease_owner IS NULL OR jobs.lease_owner = excluded.lease_owner
+            OR julianday(jobs.lease_expires) <= julianday(?4)
+            RETURNING name",
+            params![
+                name,
+                lease.worker_id,
+                lease.expires(),
+                Utc::now().to_rfc3339()
+            ],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .context("Failed to claim the job in the database")?;
+
+    Ok(claimed.is_some())
+}
+
+/// Extends the lease of a job held by the worker.
+pub async fn renew_job(db: &Arc<Mutex<Connection>>, name: &str, lease: &Lease) -> Result<()> {
+    let db = db.lock().await;
+    db.execute(
+        "UPDATE jobs SET lease_expires = ?3 WHERE name = ?1 AND lease_owner = ?2",
+        params![name, lease.worker_id, lease.expires()],
+    )
+    .context("Failed to renew the job lease")?;
+
+    Ok(())
+}
+
+/// Releases the lease of a job held by the worker, recording when it was done if it succeeded.
+pub async fn release_job(
+    db: &Arc<Mutex<Connection>>,
+    name: &str,
+    lease: &Lease,
+    done: bool,
+) -> Result<()> {
+    let db = db.lock().await;
+    db.execute(
+        "UPDATE jobs SET lease_owner = NULL, lease_expires = NULL,
+        date_done = CASE WHEN ?3 THEN ?4 ELSE date_done END
+        WHERE name = ?1 AND lease_owner = ?2",
+        params![name, lease.worker_id, done, Utc::now().to_rfc3339()],
+    )
+    .context("Failed to release the job lease")?;
+
+    Ok(())
+}
+
+/// Checks whether a job was done since a date in RFC3339 format, by any worker.
+pub async fn job_done_since(db: &Arc<Mutex<Connection>>, name: &str, since: &str) -> Result<bool> {
+    let db = db.lock().await;
+    let done = db
+        .query_row(
+            "SELECT 1 FROM jobs WHERE name = ?1 AND julianday(date_done) >= julianday(?2)",
+            params![name, since],
+            |_| Ok(()),
+        )
+        .optional()
+        .context("Failed to read the job in the database")?;
+
+    Ok(done.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::database::init::JOBS_TABLE;
+
+    fn lease(worker_id: &str, duration: i64) -> Lease {
+        Lease {
+            worker_id: worker_id.to_string(),
+            duration,
+        }
+    }
+
+    #[tokio::test]
+    async fn lets_one_worker_hold_a_job() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(JOBS_TABLE, []).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let start = Utc::now().to_rfc3339();
+        let (first, second) = (lease("first", 600), lease("second", 600));
+
+        assert!(claim_job(&db, "startup", &first).await.unwrap());
+        assert!(!claim_job(&db, "startup", &second).await.unwrap());
+        assert!(!job_done_since(&db, "startup", &start).await.unwrap());
+
+        release_job(&db, "startup", &first, true).await.unwrap();
+        assert!(job_done_since(&db, "startup", &start).await.unwrap());
+        assert!(claim_job(&db, "startup", &second).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reclaims_the_job_of_a_stopped_worker() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(JOBS_TABLE, []).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+
+        // The lease of the stopped worker has already expired
+        let (stopped, second) = (lease("stopped", -60), lease("second", 600));
+        assert!(claim_job(&db, "startup", &stopped).await.unwrap());
+        assert!(claim_job(&db, "startup", &second).await.unwrap());
+        assert!(!claim_job(&db, "startup", &stopped).await.unwrap());
+    }
+}
@@ -1,2 +1,16 @@
+pub mod developers;
+pub mod images;
 pub mod init;
 pub mod insert_sitemap_into_sql;
+pub mod jobs;
+pub mod prices;
+pub mod products;
+pub mod rate_limits;
+pub mod relations;
+pub mod removals;
+pub mod reviews;
+pub mod robots;
+pub mod schedule;
+pub mod sitemaps;
+pub mod translations;
+pub mod videos;
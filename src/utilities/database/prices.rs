@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+/// Stores the price excluding tax of a product in the currency it was scraped in.
+///
+/// Each currency is kept in its own row, scraping the page in another currency
+/// adds a row instead of replacing the price.
+pub async fn insert_price_into_sql(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    currency: &str,
+    amount: f64,
+) -> Result<()> {
+    let db = db.lock().await;
+    let date_modified = Utc::now().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO prices (ps_product_id, currency, amount, date_modified)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(ps_product_id, currency) DO UPDATE SET amount = excluded.amount,
+        date_modified = excluded.date_modified",
+        params![ps_product_id, currency, amount, date_modified],
+    )
+    .context("Failed to insert or update price in the database")?;
+
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+use crate::utilities::extract_data::ScrapedData;
+use crate::utilities::pricing::ComputedPrice;
+use crate::utilities::product_kind::ProductKind;
+use crate::utilities::ps_version::{version_key, PsVersionRange};
+
+/// A module supporting the requested PrestaShop version.
+pub struct CompatibleModule {
+    pub ps_product_id: u32,
+    pub title: String,
+    pub ps_url: String,
+    pub ps_version_min: String,
+    pub ps_version_max: Option<String>,
+}
+
+/// The Addons price of a product and the price computed for WooCommerce.
+pub struct ProductPrice {
+    pub ps_product_id: u32,
+    pub title: String,
+    pub price_ht: Option<f64>,
+    pub price_computed: Option<f64>,
+}
+
+/// Stores the title, URL and supported PrestaShop versions of a product.
+pub async fn update_product_compatibility(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    title: &str,
+    ps_url: &str,
+    ps_version_required: &str,
+    ps_version_range: Option<&PsVersionRange>,
+) -> Result<()> {
+    let db = db.lock().await;
+    let date_modified = Utc::now().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO products (ps_product_id, title, ps_url, ps_version_required, ps_version_min,
+        ps_version_max, ps_version_min_key, ps_version_max_key, date_modified)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        ON CONFLICT(ps_product_id) DO UPDATE SET title = excluded.title, ps_url = excluded.ps_url,
+        ps_version_required = excluded.ps_version_required,
+        ps_version_min = excluded.ps_version_min, ps_version_max = excluded.ps_version_max,
+        ps_version_min_key = excluded.ps_version_min_key,
+        ps_version_max_key = excluded.ps_version_max_key, date_modified = excluded.date_modified",
+        params![
+            ps_product_id,
+            title,
+            ps_url,
+            ps_version_required,
+            ps_version_range.map(|range| range.min.clone()),
+            ps_version_range.and_then(|range| range.max.clone()),
+            ps_version_range.and_then(|range| range.min_key()),
+            ps_version_range.and_then(|range| range.max_key()),
+            date_modified
+        ],
+    )
+    .context("Failed to insert or update product compatibility in the database")?;
+
+    Ok(())
+}
+
+/// Stores whether a product is a module or a theme, with the demo URL of the themes.
+pub async fn update_product_kind(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    kind: ProductKind,
+    demo_url: Option<&str>,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE products SET kind = ?2, demo_url = ?3 WHERE ps_product_id = ?1",
+        params![ps_product_id, kind.name(), demo_url],
+    )
+    .context("Failed to update product kind in the database")?;
+
+    Ok(())
+}
+
+/// Stores the details listed on the page of a product: developer, version, dates and availability.
+pub async fn update_product_details(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    details: &ScrapedData,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE products SET developer_name = ?2, module_version = ?3, last_update = ?4,
+        multistore_compatibility = ?5, publication_date = ?6, with_override = ?7, brand = ?8,
+        availability = ?9 WHERE ps_product_id = ?1",
+        params![
+            ps_product_id,
+            details.developer_name,
+            details.module_version,
+            details.last_update,
+            details.multistore_compatibility,
+            details.publication_date,
+            details.with_override,
+            details.brand,
+            details.availability
+        ],
+    )
+    .context("Failed to update product details in the database")?;
+
+    Ok(())
+}
+
+/// Stores the URL of the developer page of a product.
+pub async fn set_product_developer(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    developer_url: &str,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE products SET developer_url = ?2 WHERE ps_product_id = ?1",
+        params![ps_product_id, developer_url],
+    )
+    .context("Failed to update product developer in the database")?;
+
+    Ok(())
+}
+
+/// Stores the WooCommerce id of a product, used to resolve the relations pointing to it.
+pub async fn set_product_wc_id(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    wc_product_id: u32,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE products SET wc_product_id = ?2 WHERE ps_product_id = ?1",
+        params![ps_product_id, wc_product_id],
+    )
+    .context("Failed to update product WooCommerce id in the database")?;
+
+    Ok(())
+}
+
+/// Returns the modules whose supported range includes the version, e.g. `8.1` or `1.7.8.10`.
+///
+/// A version with fewer parts stands for the whole line, so `8.1` matches the
+/// modules supporting any `8.1.x` version.
+/// Themes are left out, the products scraped before their kind was stored are kept.
+pub async fn find_modules_supporting(
+    db: &Arc<Mutex<Connection>>,
+    version: &str,
+) -> Result<Vec<CompatibleModule>> {
+    let lower =
+        version_key(version, false).context(format!("Invalid PrestaShop version: {}", version))?;
+    let upper =
+        version_key(version, true).context(format!("Invalid PrestaShop version: {}", version))?;
+
+    let db = db.lock().await;
+    let mut stmt = db.prepare(
+        "SELECT ps_product_id, title, ps_url, ps_version_min, ps_version_max FROM products
+        WHERE (kind IS NULL OR kind = ?3)
+        AND ps_version_min_key IS NOT NULL AND ps_version_min_key <= ?1
+        AND (ps_version_max_key IS NULL OR ps_version_max_key >= ?2)
+        ORDER BY title",
+    )?;
+    let rows = stmt.query_map(params![upper, lower, ProductKind::Module.name()], |row| {
+        Ok(CompatibleModule {
+            ps_product_id: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            ps_url: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            ps_version_min: row.get(3)?,
+            ps_version_max: row.get(4)?,
+        })
+    })?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .context("Failed to read compatible modules from the database")
+}
+
+/// Stores the Addons price of a product and the price computed for WooCommerce.
+pub async fn update_product_price(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    price: &ComputedPrice,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE products SET price_ht = ?2, price_computed = ?3 WHERE ps_product_id = ?1",
+        params![ps_product_id, price.original, price.computed],
+    )
+    .context("Failed to update product price in the database")?;
+
+    Ok(())
+}
+
+/// Returns the original and computed prices of the scraped products.
+pub async fn get_product_prices(db: &Arc<Mutex<Connection>>) -> Result<Vec<ProductPrice>> {
+    let db = db.lock().await;
+    let mut stmt = db.prepare(
+        "SELECT ps_product_id, title, price_ht, price_computed FROM products
+        WHERE price_ht IS NOT NULL ORDER BY title",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ProductPrice {
+            ps_product_id: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            price_ht: row.get(2)?,
+            price_computed: row.get(3)?,
+        })
+    })?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .context("Failed to read product prices from the database")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::ps_version::parse_ps_version_range;
+
+    #[tokio::test]
+    async fn lists_only_the_modules_supporting_the_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (ps_product_id INTEGER PRIMARY KEY, title TEXT, ps_url TEXT,
+            ps_version_required TEXT, ps_version_min TEXT, ps_version_max TEXT,
+            ps_version_min_key INTEGER, ps_version_max_key INTEGER, kind TEXT, demo_url TEXT,
+            date_modified TEXT)",
+        )
+        .unwrap();
+        let db = Arc::new(Mutex::new(conn));
+
+        for (ps_product_id, title, required, kind) in [
+            (1, "Module", "1.7.0.0 - 8.1.x", Some(ProductKind::Module)),
+            (2, "Theme", "1.7.0.0 - 8.1.x", Some(ProductKind::Theme)),
+            (
+                3,
+                "Unknown minimum",
+                "1.7.8.0.1 and above",
+                Some(ProductKind::Module),
+            ),
+            (4, "Old module", "8.0.0 and above", None),
+        ] {
+            let range = parse_ps_version_range(required);
+            update_product_compatibility(&db, ps_product_id, title, "", required, range.as_ref())
+                .await
+                .unwrap();
+            if let Some(kind) = kind {
+                update_product_kind(&db, ps_product_id, kind, None)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let modules = find_modules_supporting(&db, "8.1").await.unwrap();
+        let ids: Vec<u32> = modules.iter().map(|module| module.ps_product_id).collect();
+        assert_eq!(ids, vec![1, 4]);
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+/// Takes the next request slot of a rate limit shared by the workers, in milliseconds since
+/// the Unix epoch.
+///
+/// The slot is the later of `now` and the slot following the last one taken, the next slot is
+/// moved `interval` after it in the same statement, so two workers never get the same slot.
+pub async fn take_request_slot(
+    db: &Arc<Mutex<Connection>>,
+    name: &str,
+    now: i64,
+    interval: i64,
+) -> Result<i64> {
+    let db = db.lock().await;
+
+    db.query_row(
+        "INSERT INTO rate_limits (name, next_slot) VALUES (?1, ?2 + ?3)
+        ON CONFLICT(name) DO UPDATE SET next_slot = MAX(next_slot, ?2) + ?3
+        RETURNING next_slot - ?3",
+        params![name, now, interval],
+        |row| row.get(0),
+    )
+    .context("Failed to take a request slot in the database")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::database::init::RATE_LIMITS_TABLE;
+
+    #[tokio::test]
+    async fn spaces_out_the_slots_of_every_worker() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(RATE_LIMITS_TABLE, []).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+
+        // Three requests sent at once wait for their turn
+        for expected in [1_000, 3_000, 5_000] {
+            let slot = take_request_slot(&db, "addons", 1_000, 2_000)
+                .await
+                .unwrap();
+            assert_eq!(slot, expected);
+        }
+
+        // A request sent once the slots are over is sent at once
+        let slot = take_request_slot(&db, "addons", 10_000, 2_000)
+            .await
+            .unwrap();
+        assert_eq!(slot, 10_000);
+    }
+}
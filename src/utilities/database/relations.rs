@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::extractors::ps_addons::extract_related_modules::RelatedModules;
+
+/// The upsells and cross-sells of a WooCommerce product, resolved to WooCommerce ids.
+pub struct PendingRelations {
+    pub ps_product_id: u32,
+    pub wc_product_id: u32,
+    pub upsell_ids: Vec<u32>,
+    pub cross_sell_ids: Vec<u32>,
+}
+
+impl PendingRelations {
+    /// Returns the product fields to send to WooCommerce.
+    pub fn fields(&self) -> Value {
+        json!({
+            "upsell_ids": self.upsell_ids,
+            "cross_sell_ids": self.cross_sell_ids,
+        })
+    }
+}
+
+/// Replaces the stored related modules of a product with the modules found on its page.
+pub async fn insert_relations_into_sql(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    related_modules: &RelatedModules,
+) -> Result<()> {
+    let mut db = db.lock().await;
+    let tx = db.transaction()?;
+
+    tx.execute(
+        "DELETE FROM product_relations WHERE ps_product_id = ?1",
+        params![ps_product_id],
+    )
+    .context("Failed to delete product relations from the database")?;
+
+    for (relation, ids) in [
+        ("upsell", &related_modules.upsells),
+        ("cross_sell", &related_modules.cross_sells),
+    ] {
+        // A page can suggest the module itself
+        for (position, related_ps_product_id) in
+            ids.iter().filter(|id| **id != ps_product_id).enumerate()
+        {
+            tx.execute(
+                "INSERT OR IGNORE INTO product_relations
+                (ps_product_id, related_ps_product_id, relation, position)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![ps_product_id, related_ps_product_id, relation, position],
+            )
+            .context("Failed to insert product relation in the database")?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Returns the WooCommerce products whose upsells or cross-sells changed since they were last sent.
+///
+/// Relations are resolved through the WooCommerce ids of the `products` table, the
+/// modules that aren't in WooCommerce yet are left out until a later pass. Only the
+/// given product is checked if `ps_product_id` is set.
+pub async fn get_pending_relations(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: Option<u32>,
+) -> Result<Vec<PendingRelations>> {
+    let db = db.lock().await;
+    let mut stmt = db.prepare(
+        "SELECT p.ps_product_id, p.wc_product_id, p.wc_relations, r.relation, t.wc_product_id
+        FROM products p
+        LEFT JOIN product_relations r ON r.ps_product_id = p.ps_product_id
+        LEFT JOIN products t ON t.ps_product_id = r.related_ps_product_id
+        WHERE p.wc_product_id IS NOT NULL AND (?1 IS NULL OR p.ps_product_id = ?1)
+        ORDER BY p.ps_product_id, r.relation, r.position",
+    )?;
+    let rows = stmt.query_map(params![ps_product_id], |row| {
+        Ok((
+            row.get::<_, u32>(0)?,
+            row.get::<_, u32>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<u32>>(4)?,
+        ))
+    })?;
+
+    let mut products: Vec<(PendingRelations, Option<String>)> = Vec::new();
+    for row in rows {
+        let (ps_product_id, wc_product_id, wc_relations, relation, related_wc_id) =
+            row.context("Failed to read product relations from the database")?;
+
+        if products
+            .last()
+            .is_none_or(|(pending, _)| pending.ps_product_id != ps_product_id)
+        {
+            products.push((
+                PendingRelations {
+                    ps_product_id,
+                    wc_product_id,
+                    upsell_ids: Vec::new(),
+                    cross_sell_ids: Vec::new(),
+                },
+                wc_relations,
+            ));
+        }
+
+        let (pending, _) = products.last_mut().unwrap();
+        match (relation.as_deref(), related_wc_id) {
+            (Some("upsell"), Some(id)) => pending.upsell_ids.push(id),
+            (Some("cross_sell"), Some(id)) => pending.cross_sell_ids.push(id),
+            _ => {}
+        }
+    }
+
+    // Products never synced are compared with empty relations
+    let never_synced = json!({ "upsell_ids": [], "cross_sell_ids": [] });
+
+    Ok(products
+        .into_iter()
+        .filter(|(pending, wc_relations)| {
+            let synced = wc_relations
+                .as_deref()
+                .and_then(|value| serde_json::from_str::<Value>(value).ok());
+            pending.fields() != *synced.as_ref().unwrap_or(&never_synced)
+        })
+        .map(|(pending, _)| pending)
+        .collect())
+}
+
+/// Records the upsells and cross-sells sent to WooCommerce for a product.
+pub async fn set_relations_synced(
+    db: &Arc<Mutex<Connection>>,
+    pending: &PendingRelations,
+) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE products SET wc_relations = ?2 WHERE ps_product_id = ?1",
+        params![pending.ps_product_id, pending.fields().to_string()],
+    )
+    .context("Failed to update product relations in the database")?;
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+/// A URL missing from the sitemap for several refreshes, whose removal must be confirmed.
+pub struct MissingUrl {
+    pub url: String,
+    pub lang: Option<String>,
+    pub ps_product_id: Option<u32>,
+    /// ID of the WooCommerce product, if it was created.
+    pub wc_product_id: Option<u32>,
+}
+
+/// Counts one more missing refresh for the URLs of a language not seen in the sitemap since `since`.
+///
+/// Returns the number of flagged URLs.
+pub async fn flag_missing_urls(
+    db: &Arc<Mutex<Connection>>,
+    lang: &str,
+    since: &str,
+) -> Result<usize> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE urls SET missing_count = missing_count + 1
+        WHERE lang = ?1 AND date_retired IS NULL
+        AND (last_seen_in_sitemap IS NULL OR last_seen_in_sitemap < ?2)",
+        params![lang, since],
+    )
+    .context("Failed to flag the URLs missing from the sitemap")
+}
+
+/// Returns the URLs missing from at least `threshold` consecutive sitemap refreshes.
+pub async fn get_missing_urls(
+    db: &Arc<Mutex<Connection>>,
+    threshold: i64,
+) -> Result<Vec<MissingUrl>> {
+    let db = db.lock().await;
+    let mut stmt = db.prepare(
+        "SELECT u.url, u.lang, u.ps_product_id, p.wc_product_id
+        FROM urls u LEFT JOIN products p ON p.ps_product_id = u.ps_product_id
+        WHERE u.missing_count >= ?1 AND u.date_retired IS NULL
+        ORDER BY u.id",
+    )?;
+    let rows = stmt.query_map(params![threshold], |row| {
+        Ok(MissingUrl {
+            url: row.get(0)?,
+            lang: row.get(1)?,
+            ps_product_id: row.get(2)?,
+            wc_product_id: row.get(3)?,
+        })
+    })?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .context("Failed to read missing URLs from the database")
+}
+
+/// Marks a URL as removed from Addons, it is no longer scraped.
+pub async fn set_url_retired(db: &Arc<Mutex<Connection>>, url: &str) -> Result<()> {
+    let db = db.lock().await;
+    let date_retired = Utc::now().to_rfc3339();
+
+    db.execute(
+        "UPDATE urls SET date_retired = ?2 WHERE url = ?1",
+        params![url, date_retired],
+    )
+    .context("Failed to retire URL in the database")?;
+
+    Ok(())
+}
+
+/// Clears the missing refreshes of a URL still online, e.g. only left out of the sitemap.
+pub async fn reset_missing_count(db: &Arc<Mutex<Connection>>, url: &str) -> Result<()> {
+    let db = db.lock().await;
+
+    db.execute(
+        "UPDATE urls SET missing_count = 0 WHERE url = ?1",
+        params![url],
+    )
+    .context("Failed to reset missing count in the database")?;
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+use crate::extractors::ps_addons::extract_reviews::Review;
+
+/// A review stored in the database that hasn't been imported into WooCommerce yet.
+pub struct PendingReview {
+    pub id: i64,
+    pub review: Review,
+}
+
+/// Stores the rating and the reviews of a product.
+///
+/// Reviews are identified by product, author, date and a hash of their text, so scraping
+/// a page again updates the existing rows and keeps their WooCommerce review ID.
+pub async fn insert_reviews_into_sql(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    rating_value: Option<f64>,
+    review_count: Option<u32>,
+    reviews: &[Review],
+) -> Result<()> {
+    let db = db.lock().await;
+    let date_modified = Utc::now().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO products (ps_product_id, rating_value, review_count, date_modified)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(ps_product_id) DO UPDATE SET rating_value = excluded.rating_value,
+        review_count = excluded.review_count, date_modified = excluded.date_modified",
+        params![ps_product_id, rating_value, review_count, date_modified],
+    )
+    .context("Failed to insert or update product rating in the database")?;
+
+    for review in reviews {
+        db.execute(
+            "INSERT INTO reviews (ps_product_id, author, date, rating, text, text_hash,
+            developer_reply) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(ps_product_id, author, date, text_hash) DO UPDATE
+            SET rating = excluded.rating, developer_reply = excluded.developer_reply",
+            params![
+                ps_product_id,
+                review.author,
+                review.date,
+                review.rating,
+                review.text,
+                review_text_hash(&review.text),
+                review.developer_reply
+            ],
+        )
+        .context("Failed to insert or update review in the database")?;
+    }
+
+    Ok(())
+}
+
+/// Returns a stable hash of a review text, FNV-1a in hexadecimal.
+///
+/// The hash is stored in the database, it must not change between builds.
+pub fn review_text_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Returns the reviews of a product that don't have a WooCommerce review ID yet.
+pub async fn get_pending_reviews(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+) -> Result<Vec<PendingReview>> {
+    let db = db.lock().await;
+    let mut stmt = db.prepare(
+        "SELECT id, author, date, rating, text, developer_reply FROM reviews
+        WHERE ps_product_id = ?1 AND wc_review_id IS NULL ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![ps_product_id], |row| {
+        Ok(PendingReview {
+            id: row.get(0)?,
+            review: Review {
+                author: row.get(1)?,
+                date: row.get(2)?,
+                rating: row.get(3)?,
+                text: row.get(4)?,
+                developer_reply: row.get(5)?,
+            },
+        })
+    })?;
+
+    let mut reviews = Vec::new();
+    for review in rows {
+        reviews.push(review?);
+    }
+
+    Ok(reviews)
+}
+
+/// Saves the WooCommerce review ID of an imported review, so it isn't imported twice.
+pub async fn set_review_wc_id(
+    db: &Arc<Mutex<Connection>>,
+    id: i64,
+    wc_review_id: u64,
+) -> Result<()> {
+    let db = db.lock().await;
+    db.execute(
+        "UPDATE reviews SET wc_review_id = ?1 WHERE id = ?2",
+        params![wc_review_id, id],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::database::init::REVIEWS_TABLE;
+
+    fn review(author: &str, date: &str, text: &str) -> Review {
+        Review {
+            author: author.to_string(),
+            date: date.to_string(),
+            rating: Some(5.0),
+            text: text.to_string(),
+            developer_reply: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_the_reviews_of_an_author_on_the_same_day() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (ps_product_id INTEGER PRIMARY KEY, rating_value REAL,
+            review_count INTEGER, date_modified TEXT)",
+        )
+        .unwrap();
+        conn.execute(REVIEWS_TABLE, []).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+
+        let reviews = [
+            review("Jean", "2024-05-02", "Great module"),
+            review("Jean", "2024-05-02", "Works with 8.1 too"),
+            review("Anne", "", "Fast support"),
+            review("Anne", "", "Easy to set up"),
+        ];
+        insert_reviews_into_sql(&db, 12, Some(5.0), Some(4), &reviews)
+            .await
+            .unwrap();
+        insert_reviews_into_sql(&db, 12, Some(5.0), Some(4), &reviews)
+            .await
+            .unwrap();
+
+        assert_eq!(get_pending_reviews(&db, 12).await.unwrap().len(), 4);
+        assert_eq!(review_text_hash("a"), "af63dc4c8601ec8c");
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use tokio::sync::Mutex;
+
+/// A robots.txt file stored in the database.
+pub struct CachedRobots {
+    pub content: String,
+    pub date_fetched: String,
+}
+
+/// Returns the robots.txt file last fetched from a URL, `None` if it was never fetched.
+pub async fn get_robots_txt(
+    db: &Arc<Mutex<Connection>>,
+    url: &str,
+) -> Result<Option<CachedRobots>> {
+    let db = db.lock().await;
+
+    db.query_row(
+        "SELECT content, date_fetched FROM robots WHERE url = ?1",
+        params![url],
+        |row| {
+            Ok(CachedRobots {
+                content: row.get(0)?,
+                date_fetched: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+    .context("Failed to read robots.txt from the database")
+}
+
+/// Stores a robots.txt file fetched from a URL.
+pub async fn set_robots_txt(db: &Arc<Mutex<Connection>>, url: &str, content: &str) -> Result<()> {
+    let db = db.lock().await;
+    let date_fetched = Utc::now().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO robots (url, content, date_fetched) VALUES (?1, ?2, ?3)
+        ON CONFLICT(url) DO UPDATE SET content = excluded.content,
+        date_fetched = excluded.date_fetched",
+        params![url, content, date_fetched],
+    )
+    .context("Failed to insert or update robots.txt in the database")?;
+
+    Ok(())
+}
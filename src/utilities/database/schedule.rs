@@ -0,0 +1,495 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+/// The priority rank of a URL, `NULL` if it is not due.
+///
+/// `?1` is `age_url`, `?2` is `retry_backoff` and `?3` is the start of the run: the URLs
+/// scraped during the run are no longer due.
+const PRIORITY: &str = "CASE
+        WHEN date_modified IS NULL THEN 0
+        WHEN http_code = 200 AND last_mod IS NOT NULL
+            AND julianday(last_mod) > julianday(date_modified) THEN 1
+        WHEN http_code = 200
+            AND (julianday(?3) - julianday(date_modified)) * 24 >= MAX(?1,
+            CASE change_freq
+                WHEN 'always' THEN 0
+                WHEN 'hourly' THEN 1
+                WHEN 'daily' THEN 24
+                WHEN 'weekly' THEN 168
+                WHEN 'monthly' THEN 720
+                WHEN 'yearly' THEN 8760
+                WHEN 'never' THEN NULL
+                ELSE 0
+            END) THEN 2
+        WHEN http_code IS NOT 200
+            AND (julianday(?3) - julianday(date_modified)) * 24
+            >= ?2 * (1 << MIN(MAX(fail_count - 1, 0), 7)) THEN 3
+    END";
+
+/// The URLs with their priority rank and lease, see `PRIORITY`. Retired URLs and the URLs
+/// disallowed by robots.txt are left out.
+fn url_priorities() -> String {
+    format!(
+        "SELECT id, url, lease_owner, lease_expires, {} AS priority
+        FROM urls WHERE date_retired IS NULL AND robots_disallowed = 0",
+        PRIORITY
+    )
+}
+
+/// Why a URL is due for scraping, in the order the URLs are processed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UrlPriority {
+    /// The URL was never scraped.
+    New = 0,
+    /// The sitemap `lastmod` is newer than the last scrape.
+    Updated = 1,
+    /// The last scrape is older than the sitemap `changefreq`, and at least `age_url` hours.
+    Due = 2,
+    /// The last scrape failed and its backoff delay is over.
+    Retry = 3,
+}
+
+impl UrlPriority {
+    fn from_rank(rank: i64) -> Self {
+        match rank {
+            0 => UrlPriority::New,
+            1 => UrlPriority::Updated,
+            2 => UrlPriority::Due,
+            _ => UrlPriority::Retry,
+        }
+    }
+}
+
+/// The rules deciding which URLs are due during a run.
+///
+/// A successful scrape is renewed once the sitemap `lastmod` is newer, or after the
+/// `changefreq` period, but never before `age_url` hours (`never` pages are only renewed
+/// on a newer `lastmod`). A failed scrape is retried after `retry_backoff` hours, doubled
+/// on each consecutive failure up to 128 times. Retired URLs and the URLs disallowed by
+/// robots.txt are left out.
+pub struct Schedule {
+    /// The minimum age in hours of a successful scrape before it is renewed.
+    pub age_url: i64,
+    /// The delay in hours before the first retry of a failed scrape.
+    pub retry_backoff: i64,
+    /// The start of the run in RFC3339 format, the due dates are computed from it.
+    pub run_start: String,
+}
+
+/// A URL due for scraping.
+pub struct DueUrl {
+    pub id: i64,
+    pub url: String,
+    pub priority: UrlPriority,
+}
+
+/// Position of the last URL read, the next page starts after it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UrlCursor {
+    priority: Option<UrlPriority>,
+    id: i64,
+}
+
+impl UrlCursor {
+    /// Returns the cursor placed on a URL.
+    pub fn after(due: &DueUrl) -> Self {
+        UrlCursor {
+            priority: Some(due.priority),
+            id: due.id,
+        }
+    }
+}
+
+/// The reservation of the URLs claimed by a worker.
+///
+/// A claimed URL is skipped by the other workers sharing the database until its lease
+/// expires. The lease is renewed while the worker runs, the URLs of a stopped worker are
+/// claimed again once their lease expires.
+#[derive(Clone)]
+pub struct Lease {
+    /// The identifier of the worker holding the lease.
+    pub worker_id: String,
+    /// The duration of the lease in seconds.
+    pub duration: i64,
+}
+
+impl Lease {
+    /// Returns the expiry of a lease taken or renewed now, in RFC3339 format.
+    pub fn expires(&self) -> String {
+        (Utc::now() + Duration::seconds(self.duration)).to_rfc3339()
+    }
+}
+
+/// Claims the next page of due URLs, by priority then in sitemap order.
+///
+/// The claim is a single `UPDATE ... RETURNING`: the URLs are leased to the worker in the
+/// same statement that selects them, so two workers never claim the same URL. The URLs
+/// leased to another worker are skipped until their lease expires.
+///
+/// The pages are read with keyset pagination: the URLs that stop being due while the
+/// previous pages are processed don't shift the next ones, and the URLs left due after
+/// their processing (e.g. postponed) aren't claimed again during the run. The expired
+/// leases of the other workers are reclaimed whatever the cursor.
+///
+/// # Arguments
+///
+/// * `db` - A shared, locked database connection.
+/// * `schedule` - The rules deciding which URLs are due.
+/// * `lease` - The lease of the worker.
+/// * `cursor` - The last URL of the previous page, `UrlCursor::default()` for the first page.
+/// * `limit` - The maximum number of URLs to claim.
+pub async fn claim_due_urls(
+    db: &Arc<Mutex<Connection>>,
+    schedule: &Schedule,
+    lease: &Lease,
+    cursor: UrlCursor,
+    limit: usize,
+) -> Result<Vec<DueUrl>> {
+    let db = db.lock().await;
+    let mut stmt = db.prepare(&format!(
+        "UPDATE urls SET lease_owner = ?4, lease_expires = ?5
+        WHERE id IN (
+            SELECT id FROM ({})
+            WHERE priority IS NOT NULL
+            AND (lease_expires IS NULL OR julianday(lease_expires) <= julianday(?6))
+            AND ((priority, id) > (?7, ?8) OR (lease_owner IS NOT NULL AND lease_owner != ?4))
+            ORDER BY priority, id LIMIT ?9
+        )
+        RETURNING id, url, {}",
+        url_priorities(),
+        PRIORITY
+    ))?;
+    let rows = stmt.query_map(
+        params![
+            schedule.age_url,
+            schedule.retry_backoff,
+            schedule.run_start,
+            lease.worker_id,
+            lease.expires(),
+            Utc::now().to_rfc3339(),
+            cursor.priority.map_or(-1, |priority| priority as i64),
+            cursor.id,
+            limit
+        ],
+        |row| {
+            Ok(DueUrl {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                priority: UrlPriority::from_rank(row.get(2)?),
+            })
+        },
+    )?;
+
+    // The returned rows are in no particular order
+    let mut due_urls = rows
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to claim the due URLs in the database")?;
+    due_urls.sort_by_key(|due| (due.priority, due.id));
+
+    Ok(due_urls)
+}
+
+/// Extends the leases of every URL claimed by the worker.
+pub async fn renew_leases(db: &Arc<Mutex<Connection>>, lease: &Lease) -> Result<()> {
+    let db = db.lock().await;
+    db.execute(
+        "UPDATE urls SET lease_expires = ?2 WHERE lease_owner = ?1",
+        params![lease.worker_id, lease.expires()],
+    )
+    .context("Failed to renew the URL leases")?;
+
+    Ok(())
+}
+
+/// Releases the lease of a processed URL, `None` releases every URL claimed by the worker.
+pub async fn release_leases(
+    db: &Arc<Mutex<Connection>>,
+    lease: &Lease,
+    url_id: Option<i64>,
+) -> Result<()> {
+    let db = db.lock().await;
+    db.execute(
+        "UPDATE urls SET lease_owner = NULL, lease_expires = NULL
+        WHERE lease_owner = ?1 AND (?2 IS NULL OR id = ?2)",
+        params![lease.worker_id, url_id],
+    )
+    .context("Failed to release the URL leases")?;
+
+    Ok(())
+}
+
+/// Leaves a URL disallowed by robots.txt out of the due URLs.
+///
+/// The flag is computed again on each sitemap refresh, a URL allowed again is due again.
+pub async fn set_url_disallowed(db: &Arc<Mutex<Connection>>, url_id: i64) -> Result<()> {
+    let db = db.lock().await;
+    db.execute(
+        "UPDATE urls SET robots_disallowed = 1 WHERE id = ?1",
+        params![url_id],
+    )
+    .context("Failed to flag the URL disallowed by robots.txt")?;
+
+    Ok(())
+}
+
+/// Returns the number of due URLs of each priority.
+pub async fn count_due_urls(
+    db: &Arc<Mutex<Connection>>,
+    schedule: &Schedule,
+) -> Result<Vec<(UrlPriority, usize)>> {
+    let db = db.lock().await;
+    let mut stmt = db.prepare(&format!(
+        "SELECT priority, COUNT(*) FROM ({})
+        WHERE priority IS NOT NULL GROUP BY priority ORDER BY priority",
+        url_priorities()
+    ))?;
+    let rows = stmt.query_map(
+        params![schedule.age_url, schedule.retry_backoff, schedule.run_start],
+        |row| Ok((UrlPriority::from_rank(row.get(0)?), row.get(1)?)),
+    )?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .context("Failed to count the due URLs in the database")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::database::sitemaps::touch_sitemap_urls;
+    use crate::utilities::robots::{Robots, RobotsRules};
+
+    /// A URL `(url, change_freq, last_mod, date_modified, http_code, fail_count)`, the dates
+    /// are given in hours before now.
+    type UrlRow<'a> = (
+        &'a str,
+        Option<&'a str>,
+        Option<i64>,
+        Option<i64>,
+        Option<u16>,
+        i64,
+    );
+
+    /// A database with the given URLs.
+    fn urls_db(urls: &[UrlRow]) -> Arc<Mutex<Connection>> {
+        let hours_ago =
+            |hours: Option<i64>| hours.map(|h| (Utc::now() - Duration::hours(h)).to_rfc3339());
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE urls (id INTEGER PRIMARY KEY AUTOINCREMENT, url TEXT NOT NULL UNIQUE,
+            last_mod TEXT, change_freq TEXT, http_code INTEGER, date_modified TEXT,
+            fail_count INTEGER NOT NULL DEFAULT 0, date_retired TEXT, lease_owner TEXT,
+            lease_expires TEXT, robots_disallowed INTEGER NOT NULL DEFAULT 0, sitemap_url TEXT,
+            last_seen_in_sitemap TEXT, missing_count INTEGER NOT NULL DEFAULT 0)",
+        )
+        .unwrap();
+        for (url, change_freq, last_mod, date_modified, http_code, fail_count) in urls {
+            conn.execute(
+                "INSERT INTO urls (url, change_freq, last_mod, date_modified, http_code, fail_count)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    url,
+                    change_freq,
+                    hours_ago(*last_mod),
+                    hours_ago(*date_modified),
+                    http_code,
+                    fail_count
+                ],
+            )
+            .unwrap();
+        }
+        Arc::new(Mutex::new(conn))
+    }
+
+    fn schedule() -> Schedule {
+        Schedule {
+            age_url: 24,
+            retry_backoff: 1,
+            run_start: Utc::now().to_rfc3339(),
+        }
+    }
+
+    fn lease(worker_id: &str) -> Lease {
+        Lease {
+            worker_id: worker_id.to_string(),
+            duration: 600,
+        }
+    }
+
+    async fn claim_all(db: &Arc<Mutex<Connection>>, lease: &Lease) -> Vec<(String, UrlPriority)> {
+        claim_due_urls(db, &schedule(), lease, UrlCursor::default(), 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|due| (due.url, due.priority))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn ranks_the_due_urls() {
+        let db = urls_db(&[
+            ("new", None, None, None, None, 0),
+            ("updated", Some("yearly"), Some(2), Some(5), Some(200), 0),
+            ("weekly_due", Some("weekly"), None, Some(200), Some(200), 0),
+            (
+                "weekly_fresh",
+                Some("weekly"),
+                None,
+                Some(100),
+                Some(200),
+                0,
+            ),
+            (
+                "hourly_before_age_url",
+                Some("hourly"),
+                None,
+                Some(10),
+                Some(200),
+                0,
+            ),
+            (
+                "never",
+                Some("never"),
+                Some(20_000),
+                Some(10_000),
+                Some(200),
+                0,
+            ),
+            ("retry", None, None, Some(2), Some(500), 1),
+        ]);
+
+        assert_eq!(
+            claim_all(&db, &lease("worker")).await,
+            vec![
+                ("new".to_string(), UrlPriority::New),
+                ("updated".to_string(), UrlPriority::Updated),
+                ("weekly_due".to_string(), UrlPriority::Due),
+                ("retry".to_string(), UrlPriority::Retry),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn doubles_the_retry_delay_on_each_failure() {
+        // The third consecutive failure waits 4 hours with a backoff of 1 hour
+        let db = urls_db(&[
+            ("third_failure_waiting", None, None, Some(3), Some(500), 3),
+            ("third_failure_due", None, None, Some(5), Some(500), 3),
+            ("postponed_due", None, None, Some(2), Some(425), 1),
+            ("capped", None, None, Some(129), Some(500), 20),
+        ]);
+
+        assert_eq!(
+            claim_all(&db, &lease("worker")).await,
+            vec![
+                ("third_failure_due".to_string(), UrlPriority::Retry),
+                ("postponed_due".to_string(), UrlPriority::Retry),
+                ("capped".to_string(), UrlPriority::Retry),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_each_url_to_one_worker() {
+        let db = urls_db(&[
+            ("first", None, None, None, None, 0),
+            ("second", None, None, None, None, 0),
+            ("third", None, None, None, None, 0),
+        ]);
+        let (first, second) = (lease("first"), lease("second"));
+
+        let claimed = claim_due_urls(&db, &schedule(), &first, UrlCursor::default(), 2)
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 2);
+        assert_eq!(
+            claim_all(&db, &second).await,
+            vec![("third".to_string(), UrlPriority::New)]
+        );
+        assert!(claim_all(&db, &first).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reclaims_the_urls_of_an_expired_lease() {
+        let db = urls_db(&[("stopped_worker", None, None, None, None, 0)]);
+        let stopped = Lease {
+            worker_id: "stopped".to_string(),
+            duration: -60,
+        };
+
+        assert_eq!(claim_all(&db, &stopped).await.len(), 1);
+
+        // The cursor of the other worker is already past the URL
+        let cursor = UrlCursor {
+            priority: Some(UrlPriority::Retry),
+            id: i64::MAX,
+        };
+        let reclaimed = claim_due_urls(&db, &schedule(), &lease("second"), cursor, 10)
+            .await
+            .unwrap();
+        assert_eq!(reclaimed.len(), 1);
+        assert_eq!(reclaimed[0].url, "stopped_worker");
+    }
+
+    #[tokio::test]
+    async fn leaves_the_disallowed_urls_out() {
+        let db = urls_db(&[
+            ("disallowed", None, None, None, None, 0),
+            ("allowed", None, None, None, None, 0),
+        ]);
+        let worker = lease("worker");
+
+        let claimed = claim_due_urls(&db, &schedule(), &worker, UrlCursor::default(), 1)
+            .await
+            .unwrap();
+        assert_eq!(claimed[0].url, "disallowed");
+        set_url_disallowed(&db, claimed[0].id).await.unwrap();
+        release_leases(&db, &worker, None).await.unwrap();
+
+        assert_eq!(
+            claim_all(&db, &worker).await,
+            vec![("allowed".to_string(), UrlPriority::New)]
+        );
+    }
+
+    #[tokio::test]
+    async fn claims_the_urls_allowed_again() {
+        const URL: &str = "https://addons.prestashop.com/fr/seo/9120-sitemap.html";
+        let db = urls_db(&[(URL, None, None, None, None, 0)]);
+        db.lock()
+            .await
+            .execute("UPDATE urls SET sitemap_url = 'sitemap.xml'", [])
+            .unwrap();
+        let worker = lease("worker");
+
+        let claimed = claim_all(&db, &worker).await;
+        assert_eq!(claimed.len(), 1);
+        let id = db
+            .lock()
+            .await
+            .query_row("SELECT id FROM urls", [], |row| row.get(0))
+            .unwrap();
+        set_url_disallowed(&db, id).await.unwrap();
+        release_leases(&db, &worker, None).await.unwrap();
+        assert!(claim_all(&db, &worker).await.is_empty());
+
+        // The sitemap is unchanged but robots.txt still disallows the URL
+        let disallowed = Robots::parse("User-agent: *\nDisallow: /fr/seo/").rules_for("crawler");
+        touch_sitemap_urls(&db, "sitemap.xml", &disallowed)
+            .await
+            .unwrap();
+        assert!(claim_all(&db, &worker).await.is_empty());
+
+        // The rule was removed from robots.txt
+        touch_sitemap_urls(&db, "sitemap.xml", &RobotsRules::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            claim_all(&db, &worker).await,
+            vec![(URL.to_string(), UrlPriority::New)]
+        );
+    }
+}
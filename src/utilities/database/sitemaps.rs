@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use tokio::sync::Mutex;
+
+use crate::utilities::robots::RobotsRules;
+
+/// Returns the `lastmod` of a sub-sitemap when it was last ingested.
+///
+/// Returns `None` if the sitemap was never ingested or had no `lastmod`.
+pub async fn get_sitemap_last_mod(
+    db: &Arc<Mutex<Connection>>,
+    url: &str,
+) -> Result<Option<String>> {
+    let db = db.lock().await;
+
+    db.query_row(
+        "SELECT last_mod FROM sitemaps WHERE url = ?1",
+        params![url],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(Option::flatten)
+    .context("Failed to read sitemap from the database")
+}
+
+/// Records the ingestion of a sub-sitemap with its `lastmod`.
+pub async fn set_sitemap_ingested(
+    db: &Arc<Mutex<Connection>>,
+    url: &str,
+    lang: &str,
+    last_mod: Option<&str>,
+) -> Result<()> {
+    let db = db.lock().await;
+    let date_ingested = Utc::now().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO sitemaps (url, lang, last_mod, date_ingested) VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(url) DO UPDATE SET lang = excluded.lang, last_mod = excluded.last_mod,
+        date_ingested = excluded.date_ingested",
+        params![url, lang, last_mod, date_ingested],
+    )
+    .context("Failed to insert or update sitemap in the database")?;
+
+    Ok(())
+}
+
+/// Marks the URLs of an unchanged sub-sitemap as seen, they are still listed in it.
+///
+/// The URLs are checked against the current robots.txt rules, a URL allowed again is due again.
+pub async fn touch_sitemap_urls(
+    db: &Arc<Mutex<Connection>>,
+    sitemap_url: &str,
+    robots: &RobotsRules,
+) -> Result<()> {
+    let mut db = db.lock().await;
+    let last_seen = Utc::now().to_rfc3339();
+    let transaction = db.transaction()?;
+
+    {
+        let urls = transaction
+            .prepare("SELECT id, url FROM urls WHERE sitemap_url = ?1")?
+            .query_map(params![sitemap_url], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to read the URLs of the sitemap from the database")?;
+
+        let mut statement = transaction.prepare(
+            "UPDATE urls SET last_seen_in_sitemap = ?2, missing_count = 0, robots_disallowed = ?3
+            WHERE id = ?1",
+        )?;
+        for (id, url) in urls {
+            statement.execute(params![id, last_seen, !robots.is_allowed(&url)])?;
+        }
+    }
+
+    transaction
+        .commit()
+        .context("Failed to update the URLs of the sitemap in the database")?;
+
+    Ok(())
+}
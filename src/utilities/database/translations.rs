@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+/// The content of a product page in one language.
+pub struct Translation {
+    pub lang: String,
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    pub features: String,
+}
+
+/// Stores the content of a product page in its language, next to the other languages.
+pub async fn insert_translation_into_sql(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    translation: &Translation,
+) -> Result<()> {
+    let db = db.lock().await;
+    let date_modified = Utc::now().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO translations (ps_product_id, lang, url, title, description, features,
+        date_modified)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ON CONFLICT(ps_product_id, lang) DO UPDATE SET url = excluded.url, title = excluded.title,
+        description = excluded.description, features = excluded.features,
+        date_modified = excluded.date_modified",
+        params![
+            ps_product_id,
+            translation.lang,
+            translation.url,
+            translation.title,
+            translation.description,
+            translation.features,
+            date_modified
+        ],
+    )
+    .context("Failed to insert or update translation in the database")?;
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+use crate::extractors::ps_addons::extract_videos::ProductVideo;
+
+/// Replaces the stored videos of a product with the videos found on its page.
+pub async fn insert_videos_into_sql(
+    db: &Arc<Mutex<Connection>>,
+    ps_product_id: u32,
+    videos: &[ProductVideo],
+) -> Result<()> {
+    let mut db = db.lock().await;
+    let tx = db.transaction()?;
+
+    tx.execute(
+        "DELETE FROM videos WHERE ps_product_id = ?1",
+        params![ps_product_id],
+    )
+    .context("Failed to delete videos from the database")?;
+
+    for (position, video) in videos.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO videos (ps_product_id, provider, video_id, url, embed_url, position)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                ps_product_id,
+                video.provider,
+                video.video_id,
+                video.url,
+                video.embed_url,
+                position
+            ],
+        )
+        .context("Failed to insert video in the database")?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
@@ -1,60 +1,52 @@
 use std::collections::HashMap;
 
+use colored::Colorize;
+use scraper::Html;
 use serde::Deserialize;
 
+use crate::extractors::ps_addons::extract_images::ProductImage;
+use crate::extractors::ps_addons::extract_promotion::Promotion;
+use crate::extractors::ps_addons::extract_related_modules::RelatedModules;
+use crate::extractors::ps_addons::extract_reviews::Review;
+use crate::extractors::ps_addons::extract_sections::{ProductSections, SectionKind};
+use crate::extractors::ps_addons::extract_theme::ThemeDetails;
+use crate::extractors::ps_addons::extract_videos::ProductVideo;
 use crate::extractors::ps_addons::{
-    extract_breadcrumb, extract_description, extract_developer_name, extract_features,
-    extract_image_urls, extract_last_update, extract_module_version,
-    extract_multistore_compatibility, extract_override, extract_price_ht, extract_product_id,
-    extract_ps_version_required, extract_publication_date, extract_title,
+    extract_breadcrumb, extract_developer_name, extract_images, extract_json_ld_product,
+    extract_last_update, extract_module_version, extract_multistore_compatibility,
+    extract_override, extract_price_currency, extract_price_ht, extract_product_id,
+    extract_promotion, extract_ps_version_required, extract_publication_date, extract_rating,
+    extract_related_modules, extract_reviews, extract_sections, extract_theme, extract_title,
+    extract_videos,
 };
+use crate::extractors::rules::ExtractorRules;
+use crate::utilities::extract_id_from_url::extract_id_from_url;
+use crate::utilities::product_kind::{classify_page, ProductKind};
+use crate::utilities::ps_version::{parse_ps_version_range, PsVersionRange};
 
 #[derive(Debug, Deserialize)]
 pub struct FlareSolverrResponse {
     pub solution: Solution,
-    pub status: String,
     pub message: String,
-    #[serde(rename = "startTimestamp")]
-    pub start_timestamp: u64,
-    #[serde(rename = "endTimestamp")]
-    pub end_timestamp: u64,
-    pub version: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Solution {
     pub url: String,
     pub status: u16,
-    pub headers: HashMap<String, String>,
     pub response: String,
-    pub cookies: Vec<Cookie>,
-    #[serde(rename = "userAgent")]
-    pub user_agent: String,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct Cookie {
-    pub name: Option<String>,
-    pub value: Option<String>,
-    pub domain: Option<String>,
-    pub path: Option<String>,
-    pub expires: Option<f64>,
-    pub size: Option<usize>,
-    #[serde(rename = "httpOnly")]
-    pub http_only: Option<bool>,
-    pub secure: Option<bool>,
-    pub session: Option<bool>,
-    #[serde(rename = "sameSite")]
-    pub same_site: Option<String>,
 }
 
 #[derive(Debug)]
 pub struct ScrapedData {
+    pub kind: ProductKind,
+    pub theme: Option<ThemeDetails>,
     pub breadcrumbs: Vec<HashMap<String, String>>,
     pub product_id: u32,
     pub price_ht: String,
     pub title: String,
     pub developer_name: String,
+    pub developer_url: Option<String>,
     pub ps_url: String,
     pub module_version: String,
     pub last_update: String,
@@ -64,40 +56,111 @@ pub struct ScrapedData {
     pub with_override: String,
     pub description: String,
     pub ps_version_required: String,
-    pub image_urls: Vec<String>,
+    pub ps_version_range: Option<PsVersionRange>,
+    pub images: Vec<ProductImage>,
+    pub brand: Option<String>,
+    pub price_currency: Option<String>,
+    pub promotion: Option<Promotion>,
+    pub availability: Option<String>,
+    pub rating_value: Option<f64>,
+    pub review_count: Option<u32>,
+    pub reviews: Vec<Review>,
+    pub videos: Vec<ProductVideo>,
+    pub sections: ProductSections,
+    pub related_modules: RelatedModules,
 }
 
 // Extract data scraped from server flaresolverr
-pub fn extract_data(body: &FlareSolverrResponse) -> ScrapedData {
+pub fn extract_data(
+    body: &FlareSolverrResponse,
+    rules: &ExtractorRules,
+    title_suffixes: &[String],
+) -> ScrapedData {
     // Extract data
     let ps_url = body.solution.url.clone();
-    let title = extract_title::extract_title(&body.solution.response);
-    let product_id = extract_product_id::extract_product_id(&body.solution.response);
-    let price_ht = extract_price_ht::extract_price_ht(&body.solution.response);
-    let developer_name = extract_developer_name::extract_developer_name(&body.solution.response);
-    let breadcrumbs = extract_breadcrumb::extract_breadcrumb(&body.solution.response);
-    let module_version = extract_module_version::extract_module_version(&body.solution.response);
-    let last_update = extract_last_update::extract_last_update(&body.solution.response);
+
+    // The page is parsed once for every extractor
+    let document = Html::parse_document(&body.solution.response);
+
+    // JSON-LD Product schema first, the DOM and regex extractors are fallbacks
+    let json_ld_product = extract_json_ld_product::extract_json_ld_product(&document);
+    let title = json_ld_product
+        .name
+        .as_deref()
+        .map(|name| extract_title::normalize_title(name, title_suffixes))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| extract_title::extract_title(&document, rules, title_suffixes));
+    let product_id = resolve_product_id(json_ld_product.sku.as_deref(), &ps_url)
+        .unwrap_or_else(|| extract_product_id::extract_product_id(&document, rules));
+    let price_ht = json_ld_product
+        .price
+        .clone()
+        .unwrap_or_else(|| extract_price_ht::extract_price_ht(&document, rules));
+    let price_currency = json_ld_product
+        .price_currency
+        .clone()
+        .or_else(|| extract_price_currency::extract_price_currency(&document, rules));
+    let promotion = extract_promotion::extract_promotion(&document, rules, &json_ld_product);
+    let developer_name = extract_developer_name::extract_developer_name(&document, rules);
+    let developer_url = extract_developer_name::extract_developer_url(&document, rules);
+    let breadcrumbs = extract_breadcrumb::extract_breadcrumb(&document);
+    let module_version = extract_module_version::extract_module_version(&document, rules);
+    let last_update = extract_last_update::extract_last_update(&document, rules);
     let multistore_compatibility =
-        extract_multistore_compatibility::extract_multistore_compatibility(&body.solution.response);
-    let publication_date =
-        extract_publication_date::extract_publication_date(&body.solution.response);
-    let features = extract_features::extract_features(&body.solution.response);
-    let with_override = extract_override::extract_override(&body.solution.response);
-    let description = extract_description::extract_description(&body.solution.response);
+        extract_multistore_compatibility::extract_multistore_compatibility(&document, rules);
+    let publication_date = extract_publication_date::extract_publication_date(&document, rules);
+    let with_override = extract_override::extract_override(&document, rules);
     let ps_version_required =
-        extract_ps_version_required::extract_ps_version_required(&body.solution.response);
+        extract_ps_version_required::extract_ps_version_required(&document, rules);
+
+    // Themes have their own details, and may list their supported versions apart
+    let kind = classify_page(&ps_url, &breadcrumbs, &document, rules);
+    let theme =
+        (kind == ProductKind::Theme).then(|| extract_theme::extract_theme(&document, rules));
+    let ps_version_range = theme
+        .as_ref()
+        .and_then(|theme| theme.ps_versions.clone())
+        .or_else(|| parse_ps_version_range(&ps_version_required));
+
+    // Sections of the product description, matched by their heading
+    let sections = extract_sections::extract_sections(&document, rules);
+    let description = sections
+        .get(&SectionKind::Description)
+        .cloned()
+        .unwrap_or_else(|| "Aucun contenu de description valide trouvé".to_string());
+    let features = sections
+        .get(&SectionKind::Features)
+        .cloned()
+        .unwrap_or_default();
+
+    // Rating and reviews, the page markup completes the JSON-LD data
+    let (dom_rating_value, dom_review_count) = extract_rating::extract_rating(&document, rules);
+    let rating_value = json_ld_product.rating_value.or(dom_rating_value);
+    let review_count = json_ld_product.review_count.or(dom_review_count);
+
+    let mut reviews = extract_reviews::extract_reviews(&document, rules);
+    if reviews.is_empty() {
+        reviews = json_ld_product.reviews;
+    }
 
-    // Extract urls images
-    let base_url = "https://addons.prestashop.com/";
-    let image_urls = extract_image_urls::extract_image_urls(&body.solution.response, base_url);
+    // Extract images of the product gallery
+    let images = extract_images::extract_images(&document, rules);
+
+    // Extract YouTube and Vimeo videos
+    let videos = extract_videos::extract_videos(&document, rules);
+
+    // Extract related and frequently bought together modules
+    let related_modules = extract_related_modules::extract_related_modules(&document, rules);
 
     ScrapedData {
+        kind,
+        theme,
         breadcrumbs,
         product_id,
         price_ht,
         title,
         developer_name,
+        developer_url,
         ps_url,
         module_version,
         last_update,
@@ -107,6 +170,63 @@ pub fn extract_data(body: &FlareSolverrResponse) -> ScrapedData {
         with_override,
         description,
         ps_version_required,
-        image_urls,
+        ps_version_range,
+        images,
+        brand: json_ld_product.brand,
+        price_currency,
+        promotion,
+        availability: json_ld_product.availability,
+        rating_value,
+        review_count,
+        reviews,
+        videos,
+        sections,
+        related_modules,
+    }
+}
+
+/// Returns the Addons id of a product from the JSON-LD `sku` and the id in its URL.
+///
+/// The `sku` isn't guaranteed to be the Addons id, the URL id wins when both disagree.
+/// Returns `None` when neither is found, the page markup is then used.
+fn resolve_product_id(sku: Option<&str>, ps_url: &str) -> Option<u32> {
+    let sku_id = sku.and_then(|sku| sku.trim().parse::<u32>().ok());
+    let url_id = Some(extract_id_from_url(ps_url)).filter(|id| *id != 0);
+
+    match (sku_id, url_id) {
+        (Some(sku_id), Some(url_id)) if sku_id != url_id => {
+            eprintln!(
+                "{}",
+                format!(
+                    "JSON-LD sku {} doesn't match the product id {} of {}",
+                    sku_id, url_id, ps_url
+                )
+                .yellow()
+            );
+            Some(url_id)
+        }
+        (sku_id, url_id) => sku_id.or(url_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_the_url_id_over_a_mismatched_sku() {
+        let url = "https://addons.prestashop.com/fr/seo/12345-module-seo.html";
+        assert_eq!(resolve_product_id(Some("12345"), url), Some(12345));
+        assert_eq!(resolve_product_id(Some("SEO-PRO"), url), Some(12345));
+        assert_eq!(resolve_product_id(Some("999"), url), Some(12345));
+        assert_eq!(resolve_product_id(None, url), Some(12345));
+        assert_eq!(
+            resolve_product_id(Some("999"), "https://addons.prestashop.com/fr/"),
+            Some(999)
+        );
+        assert_eq!(
+            resolve_product_id(None, "https://addons.prestashop.com/fr/"),
+            None
+        );
     }
 }
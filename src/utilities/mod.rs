@@ -1,5 +1,16 @@
+pub mod currency;
 pub mod database;
 pub mod extract_data;
 pub mod extract_id_from_url;
 pub mod generate_random_delay;
+pub mod pricing;
+pub mod product_kind;
+pub mod ps_version;
+pub mod rate_limiter;
+pub mod rehost_images;
+pub mod removed_products;
+pub mod render_content;
+pub mod robots;
+pub mod sanitize_html;
 pub mod sitemap;
+pub mod translation_plugin;
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::{
+    get_configuration_value, get_configuration_value_as_bool, get_configuration_value_as_f64,
+};
+
+/// How the computed price is rounded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rounding {
+    /// Keep the computed price, rounded to the cent.
+    Cents,
+    /// Round up to the next whole amount.
+    Integer,
+    /// Round up to the next price with the given decimals, e.g. `0.99` gives 19.99.
+    Ending(f64),
+}
+
+/// What to do with the modules without a price.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FreeModules {
+    /// Import them with a price of 0.
+    Keep,
+    /// Don't create them in WooCommerce.
+    Skip,
+    /// Import them with the given price.
+    Price(f64),
+}
+
+/// Pricing rules applied to the Addons price before it is sent to WooCommerce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PricingRules {
+    /// VAT rate of the target shop, in percent.
+    pub vat_rate: f64,
+    /// Whether the shop prices are entered tax included, VAT is only added if so.
+    pub prices_include_tax: bool,
+    /// Markup added to the Addons price, in percent.
+    pub markup_percent: f64,
+    /// Fixed margin added after the percentage markup.
+    pub markup_fixed: f64,
+    pub rounding: Rounding,
+    /// Lowest price of a paid module, after rounding.
+    pub minimum_price: f64,
+    pub free_modules: FreeModules,
+}
+
+impl Default for PricingRules {
+    fn default() -> Self {
+        Self {
+            vat_rate: 0.0,
+            prices_include_tax: false,
+            markup_percent: 0.0,
+            markup_fixed: 0.0,
+            rounding: Rounding::Cents,
+            minimum_price: 0.0,
+            free_modules: FreeModules::Keep,
+        }
+    }
+}
+
+/// The Addons price and the price computed for WooCommerce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedPrice {
+    /// Price excluding tax read on the page, `None` if it couldn't be parsed.
+    pub original: Option<f64>,
+    /// Price to send as `regular_price`, `None` if the product must not be created.
+    pub computed: Option<f64>,
+}
+
+impl ComputedPrice {
+    /// Returns the price formatted for WooCommerce, empty if the price is unknown.
+    pub fn regular_price(&self) -> String {
+        match self.original {
+            Some(_) => self
+                .computed
+                .map(|price| format!("{:.2}", price))
+                .unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    /// Returns the original and computed prices side by side, e.g. "79.99 HT -> 107.99".
+    pub fn report(&self) -> String {
+        let format_price = |price: Option<f64>| {
+            price
+                .map(|price| format!("{:.2}", price))
+                .unwrap_or_else(|| "?".to_string())
+        };
+
+        match (self.original, self.computed) {
+            (Some(_), None) => format!("{} HT -> skipped", format_price(self.original)),
+            _ => format!(
+                "{} HT -> {}",
+                format_price(self.original),
+                format_price(self.computed)
+            ),
+        }
+    }
+}
+
+/// Computes the WooCommerce price from the Addons price excluding tax.
+///
+/// The markup (percentage, then fixed margin) is applied first, then the VAT if
+/// the shop prices include tax, the rounding and the minimum price. Free modules
+/// are handled according to `free_modules` and don't get the markup or minimum price.
+pub fn compute_price(price_ht: &str, rules: &PricingRules) -> ComputedPrice {
+    let original = price_ht.trim().replace(',', ".").parse::<f64>().ok();
+
+    let computed = match original {
+        None => None,
+        Some(price) if price <= 0.0 => match rules.free_modules {
+            FreeModules::Keep => Some(0.0),
+            FreeModules::Skip => None,
+            FreeModules::Price(price) => Some(price),
+        },
+        Some(price) => {
+            let mut price = price * (1.0 + rules.markup_percent / 100.0) + rules.markup_fixed;
+            if rules.prices_include_tax {
+                price *= 1.0 + rules.vat_rate / 100.0;
+            }
+            Some(round_price(price, &rules.rounding).max(rules.minimum_price))
+        }
+    };
+
+    ComputedPrice { original, computed }
+}
+
+fn round_price(price: f64, rounding: &Rounding) -> f64 {
+    // Work in cents, so 19.99 - 0.99 isn't rounded up to 20
+    let cents = (price * 100.0).round() as i64;
+
+    let rounded = match rounding {
+        Rounding::Cents => cents,
+        Rounding::Integer => (cents + 99).div_euclid(100) * 100,
+        Rounding::Ending(ending) => {
+            let ending = (ending * 100.0).round() as i64;
+            (cents - ending + 99).div_euclid(100) * 100 + ending
+        }
+    };
+
+    rounded as f64 / 100.0
+}
+
+/// Reads the pricing rules from the `[pricing]` configuration.
+pub async fn load_pricing_rules(db: &Arc<Mutex<Connection>>) -> Result<PricingRules> {
+    let rounding = match get_configuration_value(db, "price_rounding")
+        .await?
+        .as_str()
+    {
+        "cents" | "" => Rounding::Cents,
+        "integer" => Rounding::Integer,
+        "ending" => Rounding::Ending(get_configuration_value_as_f64(db, "price_ending").await?),
+        other => {
+            return Err(anyhow::anyhow!(
+                "Invalid price_rounding value: {} (expected cents, integer or ending)",
+                other
+            ))
+        }
+    };
+
+    let free_modules = match get_configuration_value(db, "free_modules").await?.as_str() {
+        "keep" | "" => FreeModules::Keep,
+        "skip" => FreeModules::Skip,
+        "price" => {
+            FreeModules::Price(get_configuration_value_as_f64(db, "free_module_price").await?)
+        }
+        other => {
+            return Err(anyhow::anyhow!(
+                "Invalid free_modules value: {} (expected keep, skip or price)",
+                other
+            ))
+        }
+    };
+
+    Ok(PricingRules {
+        vat_rate: get_configuration_value_as_f64(db, "vat_rate").await?,
+        prices_include_tax: get_configuration_value_as_bool(db, "prices_include_tax").await?,
+        markup_percent: get_configuration_value_as_f64(db, "markup_percent").await?,
+        markup_fixed: get_configuration_value_as_f64(db, "markup_fixed").await?,
+        rounding,
+        minimum_price: get_configuration_value_as_f64(db, "minimum_price").await?,
+        free_modules,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_price_with_default_rules() {
+        let price = compute_price("79.99", &PricingRules::default());
+
+        assert_eq!(price.original, Some(79.99));
+        assert_eq!(price.regular_price(), "79.99");
+        assert_eq!(price.report(), "79.99 HT -> 79.99");
+    }
+
+    #[test]
+    fn applies_markup_vat_and_rounding() {
+        let rules = PricingRules {
+            vat_rate: 20.0,
+            prices_include_tax: true,
+            markup_percent: 10.0,
+            markup_fixed: 5.0,
+            rounding: Rounding::Ending(0.99),
+            ..PricingRules::default()
+        };
+
+        // (79.99 * 1.1 + 5) * 1.2 = 111.59, rounded up to 111.99
+        assert_eq!(compute_price("79.99", &rules).regular_price(), "111.99");
+
+        let rules = PricingRules {
+            rounding: Rounding::Integer,
+            ..rules
+        };
+        assert_eq!(compute_price("79.99", &rules).regular_price(), "112.00");
+    }
+
+    #[test]
+    fn keeps_ending_price_and_applies_minimum() {
+        let rules = PricingRules {
+            rounding: Rounding::Ending(0.99),
+            minimum_price: 9.99,
+            ..PricingRules::default()
+        };
+
+        assert_eq!(compute_price("19.99", &rules).regular_price(), "19.99");
+        assert_eq!(compute_price("20", &rules).regular_price(), "20.99");
+        assert_eq!(compute_price("4.50", &rules).regular_price(), "9.99");
+    }
+
+    #[test]
+    fn handles_free_and_unknown_prices() {
+        let mut rules = PricingRules {
+            minimum_price: 9.99,
+            ..PricingRules::default()
+        };
+        assert_eq!(compute_price("0", &rules).regular_price(), "0.00");
+
+        rules.free_modules = FreeModules::Skip;
+        let price = compute_price("0", &rules);
+        assert_eq!(price.computed, None);
+        assert_eq!(price.report(), "0.00 HT -> skipped");
+
+        rules.free_modules = FreeModules::Price(1.0);
+        assert_eq!(compute_price("0.00", &rules).regular_price(), "1.00");
+
+        let price = compute_price("", &rules);
+        assert_eq!(price.regular_price(), "");
+        assert_eq!(price.report(), "? HT -> ?");
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use scraper::Html;
+use serde::Serialize;
+
+use crate::extractors::rules::ExtractorRules;
+
+const ADDONS_ORIGIN: &str = "https://addons.prestashop.com";
+
+/// The kind of an Addons product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProductKind {
+    Module,
+    Theme,
+}
+
+impl ProductKind {
+    /// Name stored in the database, e.g. `theme`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProductKind::Module => "module",
+            ProductKind::Theme => "theme",
+        }
+    }
+}
+
+/// Classifies a sitemap URL, returns `None` if it isn't a product page.
+///
+/// Product pages follow the `/xx/category/NNN-name.html` pattern. A page is a theme
+/// if one of its categories is a theme or template category, e.g. `/fr/templates-prestashop/`,
+/// other product pages are taken as modules until their content is checked.
+pub fn classify_url(url: &str) -> Option<ProductKind> {
+    let product_page = Regex::new(r"^\d+-.*\.html$").unwrap();
+
+    let path = url.strip_prefix(ADDONS_ORIGIN)?;
+    if path.contains("/content/") {
+        return None;
+    }
+
+    // "", language, categories..., product
+    let parts: Vec<&str> = path.split('/').collect();
+    if parts.len() < 4 || !product_page.is_match(parts[parts.len() - 1]) {
+        return None;
+    }
+
+    if parts[2..parts.len() - 1]
+        .iter()
+        .any(|category| is_theme_category(category))
+    {
+        Some(ProductKind::Theme)
+    } else {
+        Some(ProductKind::Module)
+    }
+}
+
+/// Classifies a scraped product page.
+///
+/// A page is a theme if its URL is a theme URL, if one of its breadcrumb categories
+/// is a theme category (e.g. `3-templates-prestashop`), or if it has a demo link
+/// matched by the `theme_demo_url` rule.
+pub fn classify_page(
+    url: &str,
+    breadcrumbs: &[HashMap<String, String>],
+    document: &Html,
+    rules: &ExtractorRules,
+) -> ProductKind {
+    let theme_breadcrumb = breadcrumbs.iter().any(|breadcrumb| {
+        breadcrumb
+            .get("id")
+            .and_then(|id| id.trim_end_matches('/').rsplit('/').next())
+            .is_some_and(is_theme_category)
+    });
+
+    if classify_url(url) == Some(ProductKind::Theme)
+        || theme_breadcrumb
+        || rules.extract("theme_demo_url", document).is_some()
+    {
+        ProductKind::Theme
+    } else {
+        ProductKind::Module
+    }
+}
+
+/// Checks if a category slug is a theme category, e.g. `3-templates-prestashop` or `themes`.
+fn is_theme_category(slug: &str) -> bool {
+    let theme = Regex::new(r"(?i)(?:^|[-_])(?:themes?|templates?)(?:[-_]|$)").unwrap();
+    !slug.ends_with(".html") && theme.is_match(slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_sitemap_urls() {
+        assert_eq!(
+            classify_url("https://addons.prestashop.com/fr/seo/8734-seo-expert.html"),
+            Some(ProductKind::Module)
+        );
+        assert_eq!(
+            classify_url(
+                "https://addons.prestashop.com/fr/administration/1234-theme-configurator.html"
+            ),
+            Some(ProductKind::Module)
+        );
+        assert_eq!(
+            classify_url(
+                "https://addons.prestashop.com/en/templates-prestashop/fashion/50739-warehouse.html"
+            ),
+            Some(ProductKind::Theme)
+        );
+        assert_eq!(
+            classify_url("https://addons.prestashop.com/fr/3-templates-prestashop"),
+            None
+        );
+        assert_eq!(
+            classify_url("https://addons.prestashop.com/fr/content/21-contact.html"),
+            None
+        );
+        assert_eq!(
+            classify_url("https://example.com/fr/seo/8734-seo.html"),
+            None
+        );
+    }
+
+    #[test]
+    fn classifies_pages_by_breadcrumb() {
+        let rules = ExtractorRules::defaults().unwrap();
+        let breadcrumb = |id: &str| HashMap::from([("id".to_string(), id.to_string())]);
+        let url = "https://addons.prestashop.com/fr/mode-accessoires/50739-warehouse.html";
+
+        assert_eq!(
+            classify_page(
+                url,
+                &[breadcrumb(
+                    "https://addons.prestashop.com/fr/3-templates-prestashop"
+                )],
+                &Html::parse_document(""),
+                &rules
+            ),
+            ProductKind::Theme
+        );
+        assert_eq!(
+            classify_page(
+                url,
+                &[breadcrumb(
+                    "https://addons.prestashop.com/fr/2-modules-prestashop"
+                )],
+                &Html::parse_document(""),
+                &rules
+            ),
+            ProductKind::Module
+        );
+    }
+}
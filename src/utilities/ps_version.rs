@@ -0,0 +1,172 @@
+use regex::Regex;
+use serde::Serialize;
+
+/// Major lines of PrestaShop, used as attribute terms. Open ranges end at the latest one.
+pub const PS_MAJOR_LINES: [&str; 6] = ["1.4", "1.5", "1.6", "1.7", "8", "9"];
+
+/// Words meaning the range has no upper bound, e.g. "8.0.0 et supérieures".
+const OPEN_RANGE_MARKERS: [&str; 8] = [
+    "+",
+    ">",
+    "≥",
+    "supérieur",
+    "plus",
+    "above",
+    "later",
+    "higher",
+];
+
+/// Highest value of a version part, used for `x` and missing parts of an upper bound.
+const MAX_PART: i64 = 999;
+
+/// A range of supported PrestaShop versions, e.g. `1.6.1.0 - 8.1.x`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PsVersionRange {
+    /// Lowest supported version, as written on the page.
+    pub min: String,
+    /// Highest supported version, as written on the page, `None` if the range is open.
+    pub max: Option<String>,
+}
+
+impl PsVersionRange {
+    /// Sortable key of the lowest supported version, `None` if it can't be parsed.
+    pub fn min_key(&self) -> Option<i64> {
+        version_key(&self.min, false)
+    }
+
+    /// Sortable key of the highest supported version, `None` if the range is open.
+    pub fn max_key(&self) -> Option<i64> {
+        self.max.as_deref().and_then(|max| version_key(max, true))
+    }
+
+    /// Returns the major lines with at least one supported version, e.g. `["1.7", "8"]`.
+    ///
+    /// A range whose lowest version can't be parsed supports no line.
+    pub fn major_lines(&self) -> Vec<&'static str> {
+        let Some(min_key) = self.min_key() else {
+            return Vec::new();
+        };
+        let max_key = self.max_key().unwrap_or(i64::MAX);
+
+        PS_MAJOR_LINES
+            .into_iter()
+            .filter(|line| {
+                let line_min = version_key(line, false).unwrap_or(0);
+                let line_max = version_key(line, true).unwrap_or(0);
+                line_min <= max_key && line_max >= min_key
+            })
+            .collect()
+    }
+}
+
+/// Parses the text of the "Version de PrestaShop requise" field.
+///
+/// Handles ranges (`1.6.1.0 - 8.1.x`), open ranges (`8.0.0 et supérieures`,
+/// `1.7+`) and single versions or lines (`1.7.x`). Returns `None` if no version is found.
+pub fn parse_ps_version_range(text: &str) -> Option<PsVersionRange> {
+    let version = Regex::new(r"\d+(?:\.(?:\d+|[xX*]))*").unwrap();
+    let versions: Vec<String> = version
+        .find_iter(text)
+        .map(|m| m.as_str().to_lowercase().replace('*', "x"))
+        .collect();
+
+    match versions.as_slice() {
+        [] => None,
+        [single] => {
+            let lowercase = text.to_lowercase();
+            let open = OPEN_RANGE_MARKERS
+                .iter()
+                .any(|marker| lowercase.contains(marker));
+            Some(PsVersionRange {
+                min: single.clone(),
+                max: (!open).then(|| single.clone()),
+            })
+        }
+        [min, .., max] => {
+            let (min, max) = if version_key(min, false) <= version_key(max, false) {
+                (min, max)
+            } else {
+                (max, min)
+            };
+            Some(PsVersionRange {
+                min: min.clone(),
+                max: Some(max.clone()),
+            })
+        }
+    }
+}
+
+/// Returns a sortable key of a version, e.g. `1.7.8.10` gives `1_007_008_010`.
+///
+/// `x` and missing parts are the lowest value for a lower bound and the highest
+/// for an upper bound, so `8.1` covers `8.1.0.0` to `8.1.999.999`.
+pub fn version_key(version: &str, upper: bool) -> Option<i64> {
+    let parts: Vec<&str> = version.trim().split('.').collect();
+    if parts.is_empty() || parts.len() > 4 {
+        return None;
+    }
+
+    let mut key = 0;
+    for index in 0..4 {
+        let part = match parts.get(index) {
+            Some(part) if part.eq_ignore_ascii_case("x") || *part == "*" => None,
+            Some(part) => Some(part.parse::<i64>().ok()?.min(MAX_PART)),
+            None => None,
+        };
+        let value = part.unwrap_or(if upper { MAX_PART } else { 0 });
+        key = key * (MAX_PART + 1) + value;
+    }
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(min: &str, max: Option<&str>) -> Option<PsVersionRange> {
+        Some(PsVersionRange {
+            min: min.to_string(),
+            max: max.map(String::from),
+        })
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(
+            parse_ps_version_range("1.6.1.0 - 8.1.x"),
+            range("1.6.1.0", Some("8.1.x"))
+        );
+        assert_eq!(
+            parse_ps_version_range("De 1.7.7.0 à 8.1.2"),
+            range("1.7.7.0", Some("8.1.2"))
+        );
+        assert_eq!(
+            parse_ps_version_range("8.0.0 et supérieures"),
+            range("8.0.0", None)
+        );
+        assert_eq!(
+            parse_ps_version_range("1.7.*"),
+            range("1.7.x", Some("1.7.x"))
+        );
+        assert_eq!(parse_ps_version_range(""), None);
+    }
+
+    #[test]
+    fn compares_versions_with_wildcards() {
+        assert!(version_key("1.7.8.10", false) > version_key("1.7.8.9", false));
+        assert!(version_key("8.1.x", true) > version_key("8.1.5", true));
+        assert_eq!(version_key("8.1", false), version_key("8.1.0.0", false));
+        assert_eq!(version_key("8.1.a", false), None);
+    }
+
+    #[test]
+    fn lists_supported_major_lines() {
+        let lines = |text| parse_ps_version_range(text).unwrap().major_lines();
+
+        assert_eq!(lines("1.6.1.0 - 8.1.x"), vec!["1.6", "1.7", "8"]);
+        assert_eq!(lines("1.7.7.0 - 1.7.8.x"), vec!["1.7"]);
+        assert_eq!(lines("8.0.0 and above"), vec!["8", "9"]);
+        assert!(lines("1.7.8.0.1 and above").is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration};
+
+use crate::utilities::database::rate_limits::take_request_slot;
+
+/// Name of the rate limit of the requests sent to Addons.
+const ADDONS_REQUESTS: &str = "addons";
+
+/// Spaces out the requests sent to Addons, shared by the concurrent tasks and the workers.
+///
+/// Each request waits for its slot, taken in the database: the slots are at least
+/// `interval` apart whatever the number of tasks and workers running.
+pub struct RateLimiter {
+    db: Arc<Mutex<Connection>>,
+    interval: Duration,
+}
+
+impl RateLimiter {
+    /// Returns a rate limiter whose requests are `interval` apart, zero doesn't limit them.
+    pub fn new(db: &Arc<Mutex<Connection>>, interval: Duration) -> Self {
+        RateLimiter {
+            db: Arc::clone(db),
+            interval,
+        }
+    }
+
+    /// Waits until the next request can be sent.
+    pub async fn wait(&self) -> Result<()> {
+        if self.interval.is_zero() {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp_millis();
+        let interval = self.interval.as_millis() as i64;
+        let slot = take_request_slot(&self.db, ADDONS_REQUESTS, now, interval).await?;
+        sleep(Duration::from_millis((slot - now).max(0) as u64)).await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use colored::Colorize;
+use rusqlite::Connection;
+use scraper::{Html, Selector};
+use tokio::sync::Mutex;
+
+use crate::utilities::database::images::{get_rehosted_image, set_rehosted_image};
+use crate::wordpress::main::{Auth, UploadImage};
+use crate::MediaResponse;
+
+/// Uploads the images embedded in the HTML content to the WordPress media library
+/// and replaces their source with the uploaded image URL.
+///
+/// Each source is uploaded once, the images already uploaded, e.g. for an earlier scrape
+/// of the product, reuse their stored URL. Images that fail to upload keep their original source.
+pub async fn rehost_images(wp: &Auth, db: &Arc<Mutex<Connection>>, html_content: &str) -> String {
+    let sources: Vec<String> = {
+        let fragment = Html::parse_fragment(html_content);
+        let selector = Selector::parse("img[src]").unwrap();
+        fragment
+            .select(&selector)
+            .filter_map(|element| element.value().attr("src").map(|src| src.to_string()))
+            .collect()
+    };
+
+    let mut uploaded: HashMap<String, String> = HashMap::new();
+    let mut content = html_content.to_string();
+
+    for src in sources {
+        if uploaded.contains_key(&src) {
+            continue;
+        }
+
+        let stored = match get_rehosted_image(db, &src).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                eprintln!("{}", format!("{:?}", e).red());
+                None
+            }
+        };
+
+        let new_src = if let Some(stored) = stored {
+            stored
+        } else {
+            println!("Uploading image from URL: {}", src);
+            match wp.upload_image(&src).await {
+                Ok(response) => match response.json::<MediaResponse>().await {
+                    Ok(media_response) => {
+                        if let Err(e) =
+                            set_rehosted_image(db, &src, &media_response.source_url).await
+                        {
+                            eprintln!("{}", format!("{:?}", e).red());
+                        }
+                        media_response.source_url
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "{}",
+                            format!("Failed to parse media response: {:?}", e).red()
+                        );
+                        continue;
+                    }
+                },
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        format!("Failed to upload image {}: {:?}", src, e).red()
+                    );
+                    continue;
+                }
+            }
+        };
+
+        // Sources are escaped in the sanitized HTML
+        content = content.replace(
+            &format!("src=\"{}\"", src.replace('&', "&amp;")),
+            &format!("src=\"{}\"", new_src.replace('&', "&amp;")),
+        );
+        uploaded.insert(src, new_src);
+    }
+
+    content
+}
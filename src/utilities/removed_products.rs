@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::get_configuration_value;
+
+/// What happens to the WooCommerce product of a module removed from Addons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovedProducts {
+    Keep,
+    Draft,
+    Private,
+    OutOfStock,
+    Delete,
+}
+
+impl RemovedProducts {
+    /// Fields updating the product, `None` if it is kept as is or deleted.
+    pub fn update_fields(self) -> Option<Value> {
+        match self {
+            RemovedProducts::Keep | RemovedProducts::Delete => None,
+            RemovedProducts::Draft => Some(json!({ "status": "draft" })),
+            RemovedProducts::Private => Some(json!({ "status": "private" })),
+            RemovedProducts::OutOfStock => Some(json!({ "stock_status": "outofstock" })),
+        }
+    }
+}
+
+/// Loads the action applied to the products removed from Addons from the configuration.
+pub async fn load_removed_products(db: &Arc<Mutex<Connection>>) -> Result<RemovedProducts> {
+    let removed_products = get_configuration_value(db, "removed_products").await?;
+
+    match removed_products.as_str() {
+        "keep" | "" => Ok(RemovedProducts::Keep),
+        "draft" => Ok(RemovedProducts::Draft),
+        "private" => Ok(RemovedProducts::Private),
+        "outofstock" => Ok(RemovedProducts::OutOfStock),
+        "delete" => Ok(RemovedProducts::Delete),
+        other => Err(anyhow::anyhow!(
+            "Invalid removed_products value: {} (expected keep, draft, private, outofstock or delete)",
+            other
+        )),
+    }
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::get_configuration_value;
+use crate::extractors::ps_addons::extract_sections::SectionKind;
+use crate::extractors::ps_addons::extract_videos::ProductVideo;
+
+/// Where a block of the page is sent, as set by `videos` and `sections` in the `[content]` section.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    /// Left out of the product.
+    None,
+    /// Appended to the description.
+    Description,
+    /// Stored in a product meta field.
+    Meta,
+}
+
+/// Reads a placement setting of the `[content]` section, fails on an unknown value.
+pub async fn load_placement(db: &Arc<Mutex<Connection>>, key: &str) -> Result<Placement> {
+    match get_configuration_value(db, key).await?.as_str() {
+        "none" | "" => Ok(Placement::None),
+        "description" => Ok(Placement::Description),
+        "meta" => Ok(Placement::Meta),
+        other => Err(anyhow!(
+            "Invalid {} value: {} (expected none, description or meta)",
+            key,
+            other
+        )),
+    }
+}
+
+/// Renders the videos as responsive embedded players, in page order.
+///
+/// Returns an empty string if there are no videos.
+pub fn render_videos(videos: &[ProductVideo]) -> String {
+    videos
+        .iter()
+        .map(|video| {
+            format!(
+                "<figure class=\"ps-product-video ps-product-video--{}\"><iframe src=\"{}\" width=\"560\" height=\"315\" title=\"{}\" loading=\"lazy\" allow=\"encrypted-media; picture-in-picture\" allowfullscreen></iframe></figure>",
+                video.provider, video.embed_url, video.url
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Renders a section of the product description under its heading.
+pub fn render_section(kind: SectionKind, content: &str) -> String {
+    format!("<h2>{}</h2>{}", kind.title(), content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_an_unknown_placement() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE configuration (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO configuration (key, value) VALUES ('videos', 'meta'), ('sections', 'page');",
+        )
+        .unwrap();
+        let db = Arc::new(Mutex::new(conn));
+
+        assert_eq!(
+            load_placement(&db, "videos").await.unwrap(),
+            Placement::Meta
+        );
+        assert!(load_placement(&db, "sections").await.is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use regex::Regex;
+use reqwest::{Client, Url};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+
+use crate::config::get_configuration::{get_configuration_value, get_flaresolverr_url};
+use crate::utilities::database::robots::{get_robots_txt, set_robots_txt};
+
+/// Hours a fetched robots.txt is used before being fetched again.
+const ROBOTS_CACHE_HOURS: i64 = 24;
+
+#[derive(Serialize)]
+struct RequestPayload<'a> {
+    cmd: &'a str,
+    url: &'a str,
+    user_agent: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ResponsePayload {
+    solution: Solution,
+}
+
+#[derive(Deserialize)]
+struct Solution {
+    status: u16,
+    response: String,
+}
+
+/// An `Allow` or `Disallow` rule of a robots.txt group.
+#[derive(Debug, Clone)]
+struct Rule {
+    allow: bool,
+    /// Length of the path pattern, the longest matching pattern decides.
+    length: usize,
+    pattern: Regex,
+}
+
+impl Rule {
+    /// Compiles a path pattern, `*` matches any characters and a trailing `$` the end of the path.
+    fn new(allow: bool, path: &str) -> Option<Self> {
+        let (path_pattern, anchored) = match path.strip_suffix('$') {
+            Some(path_pattern) => (path_pattern, true),
+            None => (path, false),
+        };
+        let pattern = format!(
+            "^{}{}",
+            path_pattern
+                .split('*')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".*"),
+            if anchored { "$" } else { "" }
+        );
+
+        Some(Rule {
+            allow,
+            length: path.len(),
+            pattern: Regex::new(&pattern).ok()?,
+        })
+    }
+}
+
+/// A group of rules and the user agents it applies to.
+#[derive(Debug, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<f64>,
+}
+
+/// A parsed robots.txt file.
+#[derive(Debug, Default)]
+pub struct Robots {
+    groups: Vec<Group>,
+    /// The sitemaps listed by the `Sitemap:` lines.
+    pub sitemaps: Vec<String>,
+}
+
+impl Robots {
+    /// Parses a robots.txt file, the unknown and malformed lines are ignored.
+    pub fn parse(content: &str) -> Self {
+        let mut robots = Robots::default();
+        // Consecutive `User-agent` lines share the group that follows them
+        let mut reading_agents = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if !reading_agents {
+                        robots.groups.push(Group::default());
+                        reading_agents = true;
+                    }
+                    if let Some(group) = robots.groups.last_mut() {
+                        group.agents.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    reading_agents = false;
+                    // An empty `Disallow` allows everything, like no rule
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let allow = key.trim().eq_ignore_ascii_case("allow");
+                    if let (Some(group), Some(rule)) =
+                        (robots.groups.last_mut(), Rule::new(allow, value))
+                    {
+                        group.rules.push(rule);
+                    }
+                }
+                "crawl-delay" => {
+                    reading_agents = false;
+                    if let (Some(group), Ok(delay)) = (robots.groups.last_mut(), value.parse()) {
+                        group.crawl_delay = Some(delay);
+                    }
+                }
+                "sitemap" => robots.sitemaps.push(value.to_string()),
+                _ => {}
+            }
+        }
+
+        robots
+    }
+
+    /// Returns the rules applying to a user agent.
+    ///
+    /// The groups naming the longest part of the user agent apply, the `*` groups otherwise.
+    /// The groups naming the same user agent are merged.
+    pub fn rules_for(&self, user_agent: &str) -> RobotsRules {
+        let user_agent = user_agent.to_lowercase();
+        let matched_agent = self
+            .groups
+            .iter()
+            .flat_map(|group| &group.agents)
+            .filter(|agent| *agent != "*" && !agent.is_empty() && user_agent.contains(*agent))
+            .max_by_key(|agent| agent.len())
+            .map_or("*", String::as_str);
+
+        let mut rules = RobotsRules::default();
+        for group in &self.groups {
+            if group.agents.iter().any(|agent| agent == matched_agent) {
+                rules.rules.extend(group.rules.iter().cloned());
+                rules.crawl_delay = rules.crawl_delay.or(group.crawl_delay);
+            }
+        }
+
+        rules
+    }
+}
+
+/// The robots.txt rules applying to the crawler.
+#[derive(Debug, Default, Clone)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+    crawl_delay: Option<f64>,
+}
+
+impl RobotsRules {
+    /// Checks whether a URL may be crawled.
+    ///
+    /// The longest pattern matching the path and query of the URL decides, `Allow` wins a tie.
+    /// A URL matching no rule is allowed.
+    pub fn is_allowed(&self, url: &str) -> bool {
+        let path = match Url::parse(url) {
+            Ok(url) => match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_string(),
+            },
+            Err(_) => url.to_string(),
+        };
+
+        self.rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(&path))
+            .max_by_key(|rule| (rule.length, rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+
+    /// Returns the minimum delay between two requests, zero without `Crawl-delay`.
+    pub fn crawl_delay(&self) -> Duration {
+        self.crawl_delay
+            .filter(|delay| delay.is_finite() && *delay > 0.0)
+            .map_or(Duration::ZERO, Duration::from_secs_f64)
+    }
+}
+
+/// Returns the robots.txt of Addons, fetched again once the cached one is older than a day.
+///
+/// The cached robots.txt is still used when it can't be fetched again, a robots.txt
+/// missing from the server (4xx) allows everything.
+///
+/// # Errors
+///
+/// Returns an error if the robots.txt can't be fetched and was never cached.
+pub async fn load_robots(db: &Arc<Mutex<Connection>>) -> Result<Robots> {
+    let robots_url = get_configuration_value(db, "robots_url").await?;
+    let cached = get_robots_txt(db, &robots_url).await?;
+
+    if let Some(cached) = &cached {
+        let date_fetched = DateTime::parse_from_rfc3339(&cached.date_fetched)?;
+        if (Utc::now() - date_fetched.with_timezone(&Utc)).num_hours() < ROBOTS_CACHE_HOURS {
+            return Ok(Robots::parse(&cached.content));
+        }
+    }
+
+    match fetch_robots_txt(db, &robots_url).await {
+        Ok(content) => {
+            set_robots_txt(db, &robots_url, &content).await?;
+            Ok(Robots::parse(&content))
+        }
+        Err(e) => match cached {
+            Some(cached) => {
+                eprintln!(
+                    "{}",
+                    format!("Failed to fetch robots.txt, using the cached one: {:?}", e).red()
+                );
+                Ok(Robots::parse(&cached.content))
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Returns the robots.txt rules applying to the configured user agent.
+pub async fn load_robots_rules(db: &Arc<Mutex<Connection>>) -> Result<RobotsRules> {
+    let user_agent = get_configuration_value(db, "user_agent").await?;
+    Ok(load_robots(db).await?.rules_for(&user_agent))
+}
+
+/// Fetches the robots.txt file via Flaresolverr and returns its content.
+async fn fetch_robots_txt(db: &Arc<Mutex<Connection>>, robots_url: &str) -> Result<String> {
+    let flaresolverr_url = get_flaresolverr_url(db).await?;
+    let user_agent = get_configuration_value(db, "user_agent").await?;
+
+    // Create an HTTP client
+    let client = Client::new();
+
+    // Prepare the request payload for robots.txt
+    let robots_payload = RequestPayload {
+        cmd: "request.get",
+        url: robots_url,
+        user_agent: &user_agent,
+    };
+
+    // Send the request via Flaresolverr for robots.txt
+    let robots_response = client
+        .post(&flaresolverr_url)
+        .json(&robots_payload)
+        .send()
+        .await
+        .context("Failed to send request to Flaresolverr")?;
+
+    let raw_robots_response = robots_response
+        .text()
+        .await
+        .context("Failed to read raw response body")?;
+
+    let robots_response_payload: ResponsePayload = serde_json::from_str(&raw_robots_response)
+        .context("Failed to parse Flaresolverr response as JSON")?;
+
+    let solution = robots_response_payload.solution;
+    match solution.status {
+        200..=299 => {}
+        400..=499 => return Ok(String::new()),
+        status => return Err(anyhow::anyhow!("HTTP {}: {}", status, robots_url)),
+    }
+
+    // Extract content from <pre> tags, the browser escapes the text it displays
+    let pre_content = extract_pre_content(&solution.response)
+        .context("Failed to extract content from <pre> tags")?;
+
+    Ok(quick_xml::escape::unescape(&pre_content)
+        .map(|content| content.into_owned())
+        .unwrap_or(pre_content))
+}
+
+/// Extracts content inside <pre> tags from the given HTML string.
+fn extract_pre_content(html: &str) -> Option<String> {
+    let pre_re = Regex::new(r"(?s)<pre.*?>(.*?)</pre>").ok()?;
+    pre_re
+        .captures(html)
+        .and_then(|caps| caps.get(1).map(|m| m.as_str().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS_TXT: &str = "
+User-agent: *
+Disallow: /*?order=
+Disallow: /*.pdf$
+Disallow: /en/search
+Allow: /en/search/help
+Crawl-delay: 2
+
+User-agent: Googlebot
+User-agent: Chrome
+Disallow: /fr/
+
+Sitemap: https://addons.prestashop.com/sitemap.xml
+";
+
+    #[test]
+    fn applies_the_longest_matching_rule() {
+        let robots = Robots::parse(ROBOTS_TXT);
+        assert_eq!(
+            robots.sitemaps,
+            vec!["https://addons.prestashop.com/sitemap.xml"]
+        );
+
+        let rules = robots.rules_for("MyCrawler/1.0");
+        assert!(rules.is_allowed("https://addons.prestashop.com/fr/seo/123-module.html"));
+        assert!(!rules.is_allowed("https://addons.prestashop.com/en/search?q=seo"));
+        assert!(rules.is_allowed("https://addons.prestashop.com/en/search/help"));
+        assert!(!rules.is_allowed("https://addons.prestashop.com/en/2-modules?order=price"));
+        assert!(!rules.is_allowed("https://addons.prestashop.com/doc/guide.pdf"));
+        assert!(rules.is_allowed("https://addons.prestashop.com/doc/guide.pdf.html"));
+        assert_eq!(rules.crawl_delay(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn selects_the_group_of_the_user_agent() {
+        let robots = Robots::parse(ROBOTS_TXT);
+        let rules = robots.rules_for("Mozilla/5.0 (Windows NT 10.0) Chrome/91.0 Safari/537.36");
+        assert!(!rules.is_allowed("https://addons.prestashop.com/fr/seo/123-module.html"));
+        assert!(rules.is_allowed("https://addons.prestashop.com/en/search?q=seo"));
+        assert_eq!(rules.crawl_delay(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use ego_tree::NodeRef;
+use regex::Regex;
+use rusqlite::Connection;
+use scraper::{Html, Node};
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::get_configuration_value;
+
+const ADDONS_ORIGIN: &str = "https://addons.prestashop.com";
+const ADDONS_HTTP_ORIGIN: &str = "http://addons.prestashop.com";
+
+/// Tags kept in the sanitized HTML, with their allowed attributes.
+const ALLOWED_TAGS: [(&str, &[&str]); 30] = [
+    ("p", &[]),
+    ("br", &[]),
+    ("strong", &[]),
+    ("b", &[]),
+    ("em", &[]),
+    ("i", &[]),
+    ("u", &[]),
+    ("s", &[]),
+    ("sub", &[]),
+    ("sup", &[]),
+    ("ul", &[]),
+    ("ol", &[]),
+    ("li", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("blockquote", &[]),
+    ("pre", &[]),
+    ("code", &[]),
+    ("a", &["href", "title"]),
+    ("img", &["src", "alt", "title", "width", "height"]),
+    ("table", &[]),
+    ("thead", &[]),
+    ("tbody", &[]),
+    ("tfoot", &[]),
+    ("tr", &[]),
+    ("th", &["colspan", "rowspan"]),
+    ("td", &["colspan", "rowspan"]),
+];
+
+/// Tags removed with their content, everything else not allowed is unwrapped.
+const REMOVED_TAGS: [&str; 12] = [
+    "script", "style", "iframe", "object", "embed", "noscript", "form", "input", "button",
+    "select", "textarea", "template",
+];
+
+/// Elements without closing tag.
+const VOID_TAGS: [&str; 2] = ["br", "img"];
+
+/// What to do with the links pointing back to PrestaShop Addons.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddonsLinks {
+    /// Keep the links as they are.
+    Keep,
+    /// Remove the links and keep their text.
+    Strip,
+    /// Replace the Addons origin with the given URL.
+    Rewrite(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SanitizeOptions {
+    pub addons_links: AddonsLinks,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        Self {
+            addons_links: AddonsLinks::Keep,
+        }
+    }
+}
+
+/// Reads the sanitizer options from the `addons_links` and `addons_links_rewrite_url` configuration.
+pub async fn load_sanitize_options(db: &Arc<Mutex<Connection>>) -> Result<SanitizeOptions> {
+    let addons_links = get_configuration_value(db, "addons_links").await?;
+
+    let addons_links = match addons_links.as_str() {
+        "keep" | "" => AddonsLinks::Keep,
+        "strip" => AddonsLinks::Strip,
+        "rewrite" => {
+            AddonsLinks::Rewrite(get_configuration_value(db, "addons_links_rewrite_url").await?)
+        }
+        other => {
+            return Err(anyhow::anyhow!(
+                "Invalid addons_links value: {} (expected keep, strip or rewrite)",
+                other
+            ))
+        }
+    };
+
+    Ok(SanitizeOptions { addons_links })
+}
+
+/// Sanitizes HTML scraped from the product page before it is sent to WooCommerce.
+///
+/// Only the allowlisted tags and attributes are kept: scripts, iframes, forms and
+/// their content are removed, other tags such as `div` or `span` are unwrapped,
+/// inline styles and event handlers are dropped. URLs are restricted to http(s)
+/// and mailto, relative URLs are resolved against PrestaShop Addons, and the links
+/// back to Addons are handled according to `options`. Whitespace is normalized.
+pub fn sanitize_html(html_content: &str, options: &SanitizeOptions) -> String {
+    let fragment = Html::parse_fragment(html_content);
+    let mut output = String::new();
+
+    for child in fragment.root_element().children() {
+        write_node(child, &mut output, options, false);
+    }
+
+    normalize_whitespace(&output)
+}
+
+fn write_node(node: NodeRef<Node>, output: &mut String, options: &SanitizeOptions, in_pre: bool) {
+    match node.value() {
+        Node::Text(text) => {
+            if in_pre {
+                output.push_str(&escape(text));
+            } else {
+                let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !collapsed.is_empty() {
+                    if text.starts_with(char::is_whitespace) {
+                        output.push(' ');
+                    }
+                    output.push_str(&escape(&collapsed));
+                    if text.ends_with(char::is_whitespace) {
+                        output.push(' ');
+                    }
+                } else if !text.is_empty() {
+                    output.push(' ');
+                }
+            }
+        }
+        Node::Element(element) => {
+            let name = element.name();
+
+            if REMOVED_TAGS.contains(&name) {
+                return;
+            }
+
+            let allowed_attributes = ALLOWED_TAGS
+                .iter()
+                .find(|(tag, _)| *tag == name)
+                .map(|(_, attributes)| *attributes);
+
+            let write_children = |output: &mut String| {
+                for child in node.children() {
+                    write_node(child, output, options, in_pre || name == "pre");
+                }
+            };
+
+            let Some(allowed_attributes) = allowed_attributes else {
+                // Unwrap the tag, keep its content
+                write_children(output);
+                return;
+            };
+
+            // Links without a usable URL and stripped Addons links are unwrapped
+            if name == "a" {
+                let keep = match element.attr("href").and_then(clean_url) {
+                    Some(url) => {
+                        !(is_addons_url(&url) && options.addons_links == AddonsLinks::Strip)
+                    }
+                    None => false,
+                };
+                if !keep {
+                    write_children(output);
+                    return;
+                }
+            }
+
+            let mut attributes = String::new();
+            for &attribute in allowed_attributes {
+                let Some(value) = element.attr(attribute) else {
+                    continue;
+                };
+
+                let value = match attribute {
+                    "href" | "src" => match clean_url(value) {
+                        Some(url) if attribute == "href" => rewrite_addons_url(url, options),
+                        Some(url) => url,
+                        None => continue,
+                    },
+                    _ => value.to_string(),
+                };
+
+                attributes.push_str(&format!(" {}=\"{}\"", attribute, escape(&value)));
+            }
+
+            // Images without source are dropped
+            if name == "img" && !attributes.contains(" src=\"") {
+                return;
+            }
+
+            output.push_str(&format!("<{}{}>", name, attributes));
+            if VOID_TAGS.contains(&name) {
+                return;
+            }
+            write_children(output);
+            output.push_str(&format!("</{}>", name));
+        }
+        _ => {}
+    }
+}
+
+fn is_addons_url(url: &str) -> bool {
+    url.strip_prefix(ADDONS_ORIGIN)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?', '#']))
+}
+
+fn rewrite_addons_url(url: String, options: &SanitizeOptions) -> String {
+    match &options.addons_links {
+        AddonsLinks::Rewrite(base) if is_addons_url(&url) => {
+            format!(
+                "{}{}",
+                base.trim_end_matches('/'),
+                &url[ADDONS_ORIGIN.len()..]
+            )
+        }
+        _ => url,
+    }
+}
+
+/// Returns the absolute URL if its scheme is allowed, relative URLs are resolved against Addons.
+fn clean_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let lowercase = url.to_lowercase();
+
+    if lowercase.starts_with("https://")
+        || lowercase.starts_with("http://")
+        || lowercase.starts_with("mailto:")
+        || url.starts_with('#')
+    {
+        // Addons links are always served over https
+        match url.get(..ADDONS_HTTP_ORIGIN.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(ADDONS_HTTP_ORIGIN) => Some(format!(
+                "{}{}",
+                ADDONS_ORIGIN,
+                &url[ADDONS_HTTP_ORIGIN.len()..]
+            )),
+            _ => Some(url.to_string()),
+        }
+    } else if let Some(path) = url.strip_prefix("//") {
+        Some(format!("https://{}", path))
+    } else if url.starts_with('/') {
+        Some(format!("{}{}", ADDONS_ORIGIN, url))
+    } else {
+        // javascript:, data:, vbscript: and other schemes
+        None
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Removes the whitespace around block tags and the empty paragraphs.
+fn normalize_whitespace(html: &str) -> String {
+    let block_tags = Regex::new(
+        r"\s*(</?(?:p|br|ul|ol|li|h[2-6]|blockquote|pre|table|thead|tbody|tfoot|tr|th|td)(?:\s[^>]*)?>)\s*",
+    )
+    .unwrap();
+    let empty_paragraphs = Regex::new(r"<p></p>").unwrap();
+
+    let html = block_tags.replace_all(html, "$1");
+    let html = empty_paragraphs.replace_all(&html, "");
+    html.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(html: &str) -> String {
+        sanitize_html(html, &SanitizeOptions::default())
+    }
+
+    #[test]
+    fn removes_scripts_iframes_and_handlers() {
+        let html = r#"<div><p onclick="alert(1)" style="color:red">Hello <script>alert(1)</script></p>
+            <iframe src="https://evil.com"></iframe><a href="javascript:alert(1)">link</a></div>"#;
+
+        assert_eq!(sanitize(html), "<p>Hello</p>link");
+    }
+
+    #[test]
+    fn unwraps_unknown_tags_and_normalizes_whitespace() {
+        let html = "<div>\n  <p>Créez   un <span class=\"x\"><strong>blog</strong></span>\n complet</p>\n  <p> </p>\n<ul>\n <li>Un</li>\n <li>Deux</li>\n</ul></div>";
+
+        assert_eq!(
+            sanitize(html),
+            "<p>Créez un <strong>blog</strong> complet</p><ul><li>Un</li><li>Deux</li></ul>"
+        );
+    }
+
+    #[test]
+    fn keeps_allowed_attributes_and_resolves_relative_urls() {
+        let html = r#"<img src="/img/pico/1.jpg" alt="Capture" class="x" onerror="x()"><a href="https://example.com/doc?a=1&b=2" target="_blank">Doc</a>"#;
+
+        assert_eq!(
+            sanitize(html),
+            r#"<img src="https://addons.prestashop.com/img/pico/1.jpg" alt="Capture"><a href="https://example.com/doc?a=1&amp;b=2">Doc</a>"#
+        );
+    }
+
+    #[test]
+    fn handles_addons_links_according_to_options() {
+        let html = r#"<p><a href="https://addons.prestashop.com/fr/123-module.html">Module</a> <a href="/fr/contact">Contact</a> <a href="https://example.com">Site</a></p>"#;
+
+        let strip = SanitizeOptions {
+            addons_links: AddonsLinks::Strip,
+        };
+        assert_eq!(
+            sanitize_html(html, &strip),
+            r#"<p>Module Contact <a href="https://example.com">Site</a></p>"#
+        );
+
+        let rewrite = SanitizeOptions {
+            addons_links: AddonsLinks::Rewrite("https://shop.example.com/".to_string()),
+        };
+        assert_eq!(
+            sanitize_html(html, &rewrite),
+            r#"<p><a href="https://shop.example.com/fr/123-module.html">Module</a> <a href="https://shop.example.com/fr/contact">Contact</a> <a href="https://example.com">Site</a></p>"#
+        );
+    }
+
+    #[test]
+    fn escapes_text() {
+        assert_eq!(
+            sanitize("<p>1 &lt; 2 &amp; 3</p>"),
+            "<p>1 &lt; 2 &amp; 3</p>"
+        );
+    }
+}
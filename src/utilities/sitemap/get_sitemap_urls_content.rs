@@ -1,135 +1,123 @@
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use quick_xml::events::Event;
-use quick_xml::Reader;
+use colored::Colorize;
 use regex::Regex;
-use reqwest::Client;
 use rusqlite::Connection;
-use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
-use crate::config::get_configuration::get_configuration_value;
-
-#[derive(Serialize)]
-struct RequestPayload<'a> {
-    cmd: &'a str,
-    url: &'a str,
-    user_agent: &'a str,
-}
-
-#[derive(Deserialize)]
-struct ResponsePayload {
-    solution: Solution,
+use crate::utilities::database::sitemaps::get_sitemap_last_mod;
+use crate::utilities::rate_limiter::RateLimiter;
+use crate::utilities::robots::RobotsRules;
+use crate::utilities::sitemap::sitemap_xml::{
+    fetch_sitemap, SitemapIndexEntry, SitemapItem, SitemapUrl,
+};
+
+/// A sub-sitemap of a language, with its `lastmod` from the sitemap index.
+pub struct ChildSitemap {
+    pub url: String,
+    pub last_mod: Option<String>,
+    pub content: ChildContent,
 }
 
-#[derive(Deserialize)]
-struct Solution {
-    response: String,
+/// The content of a sub-sitemap.
+pub enum ChildContent {
+    /// The pages listed in the sitemap.
+    Changed(Vec<SitemapUrl>),
+    /// The `lastmod` of the sitemap hasn't changed since its last ingestion.
+    Unchanged,
+    /// The sitemap couldn't be fetched, it is left for the next update.
+    Failed,
 }
 
-/// Fetches the sitemap URLs based on the given language, retrieves the content via Flaresolverr, and returns the cleaned XML response.
+/// Fetches the sitemaps of the given language and returns their pages.
+///
+/// Every sub-sitemap of the language is fetched in turn, e.g. `sitemap_fr_1.xml` and `sitemap_fr_2.xml`,
+/// except those whose `lastmod` hasn't changed since their last ingestion. A sub-sitemap that fails
+/// to be fetched is reported and left for the next update.
 ///
 /// # Arguments
 ///
-/// * `sitemap_index_content` - The content of the sitemap index as a &str.
+/// * `limiter` - The rate limiter of the requests sent to Addons.
+/// * `robots` - The robots.txt rules the sitemaps must be allowed by.
+/// * `sitemaps` - The sitemaps listed in the sitemap index.
 /// * `sitemap_lang` - The language code to filter the sitemap URLs.
 ///
 /// # Errors
 ///
 /// Returns an error if any of the following operations fail:
 ///
-/// - Parsing the sitemap content.
-/// - Sending the GET request to the sitemap URL.
-/// - Reading the response body.
+/// - Reading the last ingestion of the sitemaps.
+/// - Finding at least one sitemap of the language.
 ///
 /// # Returns
 ///
-/// If successful, returns the cleaned XML content as a `String`.
+/// If successful, returns every sitemap of the language with its pages.
 pub async fn get_sitemap_urls_content(
     db: &Arc<Mutex<Connection>>,
-    sitemap_index_content: &str,
+    limiter: &RateLimiter,
+    robots: &RobotsRules,
+    sitemaps: &[SitemapIndexEntry],
     sitemap_lang: &str,
-) -> Result<String> {
-    let flaresolverr_url = get_configuration_value(db, "flaresolverr_url").await?;
-    let user_agent = get_configuration_value(db, "user_agent").await?;
-
-    // Create an HTTP client
-    let client = Client::new();
-
-    let mut reader = Reader::from_str(sitemap_index_content);
-    reader.config_mut().trim_text(true);
-
-    let mut buf = Vec::new();
-    let mut in_url_tag = false;
-
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) if e.name().as_ref() == b"sitemap" => {
-                in_url_tag = true;
-            }
-            Ok(Event::End(ref e)) if e.name().as_ref() == b"sitemap" => {
-                in_url_tag = false;
-            }
-            Ok(Event::Start(ref e)) if e.name().as_ref() == b"loc" && in_url_tag => {
-                let url = reader
-                    .read_text(e.name())
-                    .context("Failed to read loc text")?;
-                if url.contains(&format!("sitemap_{}", sitemap_lang)) {
-                    // Prepare the request payload for the sitemap URL
-                    let sitemap_payload = RequestPayload {
-                        cmd: "request.get",
-                        url: &url,
-                        user_agent: &user_agent,
-                    };
-
-                    // Send the request via Flaresolverr for the sitemap URL
-                    let sitemap_response = client
-                        .post(&flaresolverr_url)
-                        .json(&sitemap_payload)
-                        .send()
-                        .await
-                        .context("Failed to send request to Flaresolverr")?;
-
-                    let raw_sitemap_response = sitemap_response
-                        .text()
-                        .await
-                        .context("Failed to read raw response body")?;
-
-                    let sitemap_response_payload: ResponsePayload =
-                        serde_json::from_str(&raw_sitemap_response)
-                            .context("Failed to parse Flaresolverr response as JSON")?;
-
-                    let sitemap_body = sitemap_response_payload.solution.response;
-
-                    // Extract and clean the XML content
-                    let cleaned_sitemap_content = extract_xml_content(&sitemap_body)
-                        .context("Failed to extract XML content from response")?;
+) -> Result<Vec<ChildSitemap>> {
+    // "sitemap_fr" must not match the sitemaps of "fr-ca" or "fra"
+    let sitemap_name = Regex::new(&format!(
+        r"sitemap_{}(?:[_.]|$)",
+        regex::escape(sitemap_lang)
+    ))
+    .context("Failed to compile regex")?;
+
+    let sitemaps: Vec<&SitemapIndexEntry> = sitemaps
+        .iter()
+        .filter(|sitemap| sitemap_name.is_match(&sitemap.loc))
+        .collect();
+
+    if sitemaps.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No matching sitemap URL found for language: {}",
+            sitemap_lang
+        ));
+    }
 
-                    // Return the cleaned XML content
-                    return Ok(cleaned_sitemap_content);
+    let mut children = Vec::new();
+    for sitemap in sitemaps {
+        let url = sitemap.loc.clone();
+        let last_mod = sitemap.last_mod.clone();
+
+        // Skip the sitemaps unchanged since their last ingestion
+        let content = if last_mod.is_some() && get_sitemap_last_mod(db, &url).await? == last_mod {
+            println!(
+                "{}",
+                format!("Sitemap unchanged since the last ingestion: {}", url).yellow()
+            );
+            ChildContent::Unchanged
+        } else {
+            match fetch_sitemap(db, limiter, robots, &url).await {
+                Ok(items) => ChildContent::Changed(
+                    items
+                        .into_iter()
+                        .filter_map(|item| match item {
+                            SitemapItem::Url(page) => Some(page),
+                            SitemapItem::Sitemap(_) => None,
+                        })
+                        .collect(),
+                ),
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        format!("Failed to fetch sitemap {}: {:?}", url, e).red()
+                    );
+                    ChildContent::Failed
                 }
             }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(anyhow::anyhow!(
-                    "Error at position {}: {:?}",
-                    reader.buffer_position(),
-                    e
-                ))
-            }
-            _ => (),
-        }
-        buf.clear();
-    }
+        };
 
-    Err(anyhow::anyhow!("No matching sitemap URL found"))
-}
+        children.push(ChildSitemap {
+            url,
+            last_mod,
+            content,
+        });
+    }
 
-/// Extracts the content inside <urlset> tags from the given HTML string.
-fn extract_xml_content(html: &str) -> Option<String> {
-    let urlset_re = Regex::new(r"(?s)<urlset[^>]*>(.*?)</urlset>").ok()?;
-    urlset_re
-        .captures(html)
-        .and_then(|caps| caps.get(1).map(|m| m.as_str().to_string()))
+    Ok(children)
 }
@@ -1,3 +1,4 @@
 mod get_sitemap_index_content;
 mod get_sitemap_urls_content;
 pub mod sitemap_update;
+pub mod sitemap_xml;
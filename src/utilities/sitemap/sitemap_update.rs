@@ -1,22 +1,25 @@
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{Duration, Utc};
 use colored::Colorize;
-use rusqlite::Connection;
+use rusqlite::{params, Connection};
 use tokio::sync::Mutex;
 
 use crate::config::get_configuration::get_configuration_value;
 use crate::utilities::database::insert_sitemap_into_sql::insert_sitemap_into_sql;
+use crate::utilities::database::removals::flag_missing_urls;
+use crate::utilities::database::sitemaps::{set_sitemap_ingested, touch_sitemap_urls};
+use crate::utilities::rate_limiter::RateLimiter;
+use crate::utilities::robots::load_robots;
 use crate::utilities::sitemap::get_sitemap_index_content::get_sitemap_index_content;
-use crate::utilities::sitemap::get_sitemap_urls_content::get_sitemap_urls_content;
+use crate::utilities::sitemap::get_sitemap_urls_content::{get_sitemap_urls_content, ChildContent};
 
 pub async fn sitemap_update(
     db: &Arc<Mutex<Connection>>,
     sitemap_frequency_update: i64,
 ) -> Result<()> {
-    let robots_url = get_configuration_value(db, "robots_url").await?;
-    let sitemap_lang = get_configuration_value(db, "sitemap_lang").await?;
+    let sitemap_langs = load_sitemap_langs(db).await?;
 
     // Check the last sitemap update date
     let skip_sitemap = if let Ok(Some(last_insert_date)) = get_last_xml_insert_date(db).await {
@@ -42,45 +45,111 @@ pub async fn sitemap_update(
     };
 
     if !skip_sitemap {
-        // Extract content for sitemap index
-        let sitemap_index_content = match get_sitemap_index_content(db, &robots_url).await {
+        let refresh_start = Utc::now().to_rfc3339();
+
+        // Extract content for sitemap index, listed in robots.txt, whose rules apply to the
+        // sitemaps and their URLs
+        let robots = load_robots(db).await?;
+        let user_agent = get_configuration_value(db, "user_agent").await?;
+        let robots_rules = robots.rules_for(&user_agent);
+        let limiter = RateLimiter::new(db, robots_rules.crawl_delay());
+        let sitemaps = match get_sitemap_index_content(db, &limiter, &robots, &robots_rules).await {
             Ok(content) => content,
             Err(e) => {
                 eprintln!(
                     "{}",
                     format!("Failed to extract sitemap index data: {:?}", e).red()
                 );
-                return Err(e.into());
+                return Err(e);
             }
         };
 
-        // Extract content for sitemap url
-        let sitemap_urls_content =
-            match get_sitemap_urls_content(db, &sitemap_index_content, &sitemap_lang).await {
-                Ok(content) => content,
+        // The refresh date is only recorded once every sitemap was read, a failed one is
+        // fetched again on the next run
+        let mut refresh_complete = true;
+        for sitemap_lang in &sitemap_langs {
+            // Extract content for every sitemap url of the language
+            let sitemap_urls_contents = match get_sitemap_urls_content(
+                db,
+                &limiter,
+                &robots_rules,
+                &sitemaps,
+                sitemap_lang,
+            )
+            .await
+            {
+                Ok(contents) => contents,
                 Err(e) => {
                     eprintln!(
                         "{}",
                         format!("Failed to fetch sitemap url data: {:?}", e).red()
                     );
-                    return Err(e.into());
+                    return Err(e);
                 }
             };
 
-        // Insert sitemap urls into database
-        match insert_sitemap_into_sql(&db, &sitemap_urls_content).await {
-            Ok(_) => println!(
-                "{}",
-                "Added sitemap data successfully into database".green()
-            ),
-            Err(e) => {
-                eprintln!(
-                    "{}",
-                    format!("Failed to added sitemap data into database: {:?}", e).red()
-                );
-                return Err(e.into());
+            // Insert sitemap urls into database, then record the lastmod of the sitemap
+            let mut complete = true;
+            for sitemap in sitemap_urls_contents {
+                let pages = match sitemap.content {
+                    ChildContent::Changed(pages) => pages,
+                    ChildContent::Unchanged => {
+                        touch_sitemap_urls(db, &sitemap.url, &robots_rules).await?;
+                        continue;
+                    }
+                    ChildContent::Failed => {
+                        complete = false;
+                        continue;
+                    }
+                };
+
+                match insert_sitemap_into_sql(db, &sitemap.url, pages, sitemap_lang, &robots_rules)
+                    .await
+                {
+                    Ok(_) => {
+                        set_sitemap_ingested(
+                            db,
+                            &sitemap.url,
+                            sitemap_lang,
+                            sitemap.last_mod.as_deref(),
+                        )
+                        .await?;
+                        println!(
+                            "{}",
+                            format!(
+                                "Added sitemap data successfully into database: {}",
+                                sitemap.url
+                            )
+                            .green()
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "{}",
+                            format!("Failed to added sitemap data into database: {:?}", e).red()
+                        );
+                        return Err(e);
+                    }
+                }
+            }
+
+            // The URLs not listed anymore count one more missing refresh, unless a sitemap failed
+            refresh_complete &= complete;
+            if complete {
+                let missing = flag_missing_urls(db, sitemap_lang, &refresh_start).await?;
+                if missing > 0 {
+                    println!(
+                        "{}",
+                        format!("{} URLs missing from the {} sitemap", missing, sitemap_lang)
+                            .yellow()
+                    );
+                }
             }
         }
+
+        if refresh_complete {
+            set_last_xml_insert_date(db).await?;
+        }
     } else {
         println!("{}", "Skipping update sitemap".yellow());
     }
@@ -88,6 +157,12 @@ pub async fn sitemap_update(
     Ok(())
 }
 
+/// Returns the languages of the sitemaps to ingest, the first one is the main language.
+pub async fn load_sitemap_langs(db: &Arc<Mutex<Connection>>) -> Result<Vec<String>> {
+    let value = get_configuration_value(db, "sitemap_langs").await?;
+    serde_json::from_str(&value).context("Failed to parse sitemap_langs configuration")
+}
+
 async fn get_last_xml_insert_date(db: &Arc<Mutex<Connection>>) -> Result<Option<String>> {
     let db = db.lock().await;
     let mut stmt =
@@ -101,3 +176,15 @@ async fn get_last_xml_insert_date(db: &Arc<Mutex<Connection>>) -> Result<Option<
         Ok(None)
     }
 }
+
+async fn set_last_xml_insert_date(db: &Arc<Mutex<Connection>>) -> Result<()> {
+    let db = db.lock().await;
+    db.execute(
+        "INSERT INTO configuration (key, value) VALUES ('last_sitemap_insert_date', ?1)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![Utc::now().to_rfc3339()],
+    )
+    .context("Failed to insert or update last_sitemap_insert_date in the configuration table")?;
+
+    Ok(())
+}
@@ -0,0 +1,530 @@
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use flate2::bufread::GzDecoder;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+use reqwest::header::USER_AGENT;
+use reqwest::Client;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task;
+
+use crate::config::get_configuration::{get_configuration_value, get_flaresolverr_url};
+use crate::utilities::rate_limiter::RateLimiter;
+use crate::utilities::robots::RobotsRules;
+
+const SITEMAP_NS: &[u8] = b"http://www.sitemaps.org/schemas/sitemap/0.9";
+const XHTML_NS: &[u8] = b"http://www.w3.org/1999/xhtml";
+const IMAGE_NS: &[u8] = b"http://www.google.com/schemas/sitemap-image/1.1";
+
+/// Number of downloaded chunks waiting for the parser.
+const SITEMAP_CHUNKS: usize = 16;
+
+#[derive(Serialize)]
+struct RequestPayload<'a> {
+    cmd: &'a str,
+    url: &'a str,
+    user_agent: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ResponsePayload {
+    solution: Solution,
+}
+
+#[derive(Deserialize)]
+struct Solution {
+    response: String,
+}
+
+/// A sitemap listed in a sitemap index.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapIndexEntry {
+    pub loc: String,
+    pub last_mod: Option<String>,
+}
+
+/// A page listed in a sitemap.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapUrl {
+    pub loc: String,
+    pub last_mod: Option<String>,
+    pub change_freq: Option<String>,
+    /// The `xhtml:link` alternates of the page, as `(hreflang, href)`.
+    pub alternates: Vec<(String, String)>,
+    /// The images of the image sitemap extension.
+    pub images: Vec<SitemapImage>,
+}
+
+/// An image of a page, from the image sitemap extension.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SitemapImage {
+    pub loc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+}
+
+/// An entry of a sitemap index or of a sitemap.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SitemapItem {
+    Sitemap(SitemapIndexEntry),
+    Url(SitemapUrl),
+}
+
+/// Namespace of a sitemap element.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Namespace {
+    Sitemap,
+    Xhtml,
+    Image,
+    Other,
+}
+
+/// Fetches a sitemap and returns its entries.
+///
+/// The sitemap must be allowed by robots.txt, the request waits for its slot of the
+/// Crawl-delay. Gzipped sitemaps (`.xml.gz`) are downloaded directly since the browser of Flaresolverr
+/// can't display them, they are decoded and parsed while they are downloaded. The other
+/// sitemaps are fetched via Flaresolverr, whose JSON response holds the whole page. The
+/// parsing runs on a blocking thread.
+pub async fn fetch_sitemap(
+    db: &Arc<Mutex<Connection>>,
+    limiter: &RateLimiter,
+    robots: &RobotsRules,
+    url: &str,
+) -> Result<Vec<SitemapItem>> {
+    if !robots.is_allowed(url) {
+        return Err(anyhow::anyhow!("Sitemap disallowed by robots.txt: {}", url));
+    }
+    limiter.wait().await?;
+
+    let flaresolverr_url = get_flaresolverr_url(db).await?;
+    let user_agent = get_configuration_value(db, "user_agent").await?;
+
+    // Create an HTTP client
+    let client = Client::new();
+
+    if url.ends_with(".gz") {
+        let mut response = client
+            .get(url)
+            .header(USER_AGENT, &user_agent)
+            .send()
+            .await
+            .context("Failed to send request for the gzipped sitemap")?
+            .error_for_status()
+            .context("Failed to download the gzipped sitemap")?;
+
+        // The chunks are handed to the parser as they arrive
+        let (sender, receiver) = mpsc::channel(SITEMAP_CHUNKS);
+        let items =
+            task::spawn_blocking(move || read_sitemap(BufReader::new(ChunkReader::new(receiver))));
+
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => Ok(chunk.to_vec()),
+                Ok(None) => break,
+                Err(e) => Err(io::Error::other(e)),
+            };
+            let failed = chunk.is_err();
+            // The parser stops reading on an invalid sitemap
+            if sender.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+        drop(sender);
+
+        return items.await.context("Failed to parse the gzipped sitemap")?;
+    }
+
+    // Prepare the request payload for the sitemap URL
+    let sitemap_payload = RequestPayload {
+        cmd: "request.get",
+        url,
+        user_agent: &user_agent,
+    };
+
+    // Send the request via Flaresolverr for the sitemap URL
+    let sitemap_response = client
+        .post(&flaresolverr_url)
+        .json(&sitemap_payload)
+        .send()
+        .await
+        .context("Failed to send request to Flaresolverr")?;
+
+    let raw_sitemap_response = sitemap_response
+        .text()
+        .await
+        .context("Failed to read raw response body")?;
+
+    let sitemap_response_payload: ResponsePayload = serde_json::from_str(&raw_sitemap_response)
+        .context("Failed to parse Flaresolverr response as JSON")?;
+
+    let body = sitemap_response_payload.solution.response.into_bytes();
+    task::spawn_blocking(move || read_sitemap(Cursor::new(body)))
+        .await
+        .context("Failed to parse the sitemap")?
+}
+
+/// Reads the chunks of a body downloaded by an async task, from a blocking thread.
+struct ChunkReader {
+    receiver: mpsc::Receiver<io::Result<Vec<u8>>>,
+    chunk: Cursor<Vec<u8>>,
+}
+
+impl ChunkReader {
+    fn new(receiver: mpsc::Receiver<io::Result<Vec<u8>>>) -> Self {
+        ChunkReader {
+            receiver,
+            chunk: Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.chunk.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            // The body ends when the sender is dropped
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = Cursor::new(chunk?),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Parses a sitemap body and returns its entries, see `sitemap_reader`.
+fn read_sitemap<R: BufRead + 'static>(reader: R) -> Result<Vec<SitemapItem>> {
+    let mut items = Vec::new();
+    parse_sitemap(sitemap_reader(reader)?, |item| {
+        items.push(item);
+        Ok(())
+    })?;
+    Ok(items)
+}
+
+/// Returns a reader over the XML of a sitemap body.
+///
+/// The body can be raw XML, gzipped XML, or XML displayed by a browser: the page of the
+/// browser is then reduced to the `urlset` or `sitemapindex` element. Gzipped XML is
+/// decoded as it is read.
+pub fn sitemap_reader<R: BufRead + 'static>(mut body: R) -> Result<Box<dyn BufRead>> {
+    // Gzip magic number
+    if body
+        .fill_buf()
+        .context("Failed to read the sitemap")?
+        .starts_with(&[0x1f, 0x8b])
+    {
+        return Ok(Box::new(BufReader::new(GzDecoder::new(body))));
+    }
+
+    let mut content = String::new();
+    body.read_to_string(&mut content)
+        .context("Sitemap is not valid UTF-8")?;
+    let xml =
+        unwrap_browser_xml(&content).context("Failed to find the sitemap XML in the response")?;
+
+    Ok(Box::new(Cursor::new(xml.into_bytes())))
+}
+
+/// Extracts the sitemap XML from the page of a browser.
+///
+/// Chrome keeps the XML elements in the page, Firefox escapes them inside a `<pre>`.
+fn unwrap_browser_xml(content: &str) -> Option<String> {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with("<?xml")
+        || trimmed.starts_with("<urlset")
+        || trimmed.starts_with("<sitemapindex")
+    {
+        return Some(content.to_string());
+    }
+
+    for root in ["urlset", "sitemapindex"] {
+        if let Some(xml) = slice_element(content, &format!("<{}", root), &format!("</{}>", root)) {
+            return Some(xml.to_string());
+        }
+        if let Some(xml) = slice_element(
+            content,
+            &format!("&lt;{}", root),
+            &format!("&lt;/{}&gt;", root),
+        ) {
+            return quick_xml::escape::unescape(xml)
+                .ok()
+                .map(|xml| xml.to_string());
+        }
+    }
+
+    None
+}
+
+/// Returns the text from the first `start` to the last `end`, both included.
+fn slice_element<'a>(content: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let from = content.find(start)?;
+    let to = content.rfind(end)? + end.len();
+    (to > from).then(|| &content[from..to])
+}
+
+/// Parses a sitemap or a sitemap index in streaming fashion, calling `on_item` for each entry.
+///
+/// The sitemap elements are matched by namespace, with the `xhtml:link` hreflang alternates
+/// and the image sitemap extension. Elements without namespace are taken as sitemap elements,
+/// and the usual `xhtml` and `image` prefixes are accepted when their declaration was lost.
+pub fn parse_sitemap<R: BufRead>(
+    reader: R,
+    mut on_item: impl FnMut(SitemapItem) -> Result<()>,
+) -> Result<()> {
+    let mut reader = NsReader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut text = String::new();
+    let mut sitemap: Option<SitemapIndexEntry> = None;
+    let mut url: Option<SitemapUrl> = None;
+    let mut image: Option<SitemapImage> = None;
+
+    loop {
+        let (namespace, event) = match reader.read_resolved_event_into(&mut buf) {
+            Ok((ns, event)) => (ns, event),
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "Error at position {}: {:?}",
+                    reader.buffer_position(),
+                    e
+                ))
+            }
+        };
+
+        match event {
+            Event::Start(ref e) => {
+                text.clear();
+                match (element_namespace(&namespace), e.local_name().as_ref()) {
+                    (Namespace::Sitemap, b"sitemap") => sitemap = Some(Default::default()),
+                    (Namespace::Sitemap, b"url") => url = Some(Default::default()),
+                    (Namespace::Image, b"image") => image = Some(Default::default()),
+                    (Namespace::Xhtml, b"link") => push_alternate(url.as_mut(), e)?,
+                    _ => (),
+                }
+            }
+            Event::Empty(ref e) => {
+                if let (Namespace::Xhtml, b"link") =
+                    (element_namespace(&namespace), e.local_name().as_ref())
+                {
+                    push_alternate(url.as_mut(), e)?;
+                }
+            }
+            Event::Text(ref e) => {
+                text.push_str(&e.unescape().context("Failed to read sitemap text")?);
+            }
+            Event::CData(ref e) => {
+                text.push_str(&String::from_utf8_lossy(e));
+            }
+            Event::End(ref e) => {
+                let value = text.trim().to_string();
+                text.clear();
+
+                match (element_namespace(&namespace), e.local_name().as_ref()) {
+                    (Namespace::Sitemap, b"loc") => {
+                        if let Some(url) = url.as_mut() {
+                            url.loc = value;
+                        } else if let Some(sitemap) = sitemap.as_mut() {
+                            sitemap.loc = value;
+                        }
+                    }
+                    (Namespace::Sitemap, b"lastmod") => {
+                        if let Some(url) = url.as_mut() {
+                            url.last_mod = Some(value);
+                        } else if let Some(sitemap) = sitemap.as_mut() {
+                            sitemap.last_mod = Some(value);
+                        }
+                    }
+                    (Namespace::Sitemap, b"changefreq") => {
+                        if let Some(url) = url.as_mut() {
+                            url.change_freq = Some(value);
+                        }
+                    }
+                    (Namespace::Image, b"loc") => {
+                        if let Some(image) = image.as_mut() {
+                            image.loc = value;
+                        }
+                    }
+                    (Namespace::Image, b"title") => {
+                        if let Some(image) = image.as_mut() {
+                            image.title = Some(value);
+                        }
+                    }
+                    (Namespace::Image, b"caption") => {
+                        if let Some(image) = image.as_mut() {
+                            image.caption = Some(value);
+                        }
+                    }
+                    (Namespace::Image, b"image") => {
+                        if let (Some(url), Some(image)) = (url.as_mut(), image.take()) {
+                            if !image.loc.is_empty() {
+                                url.images.push(image);
+                            }
+                        }
+                    }
+                    (Namespace::Sitemap, b"url") => {
+                        if let Some(url) = url.take().filter(|url| !url.loc.is_empty()) {
+                            on_item(SitemapItem::Url(url))?;
+                        }
+                    }
+                    (Namespace::Sitemap, b"sitemap") => {
+                        if let Some(sitemap) = sitemap.take().filter(|s| !s.loc.is_empty()) {
+                            on_item(SitemapItem::Sitemap(sitemap))?;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(())
+}
+
+/// Returns the namespace of an element.
+fn element_namespace(namespace: &ResolveResult) -> Namespace {
+    match namespace {
+        ResolveResult::Bound(ns) if ns.as_ref() == SITEMAP_NS => Namespace::Sitemap,
+        ResolveResult::Bound(ns) if ns.as_ref() == XHTML_NS => Namespace::Xhtml,
+        ResolveResult::Bound(ns) if ns.as_ref() == IMAGE_NS => Namespace::Image,
+        ResolveResult::Bound(_) => Namespace::Other,
+        // Browser-displayed sitemaps can lose their namespace declarations
+        ResolveResult::Unbound => Namespace::Sitemap,
+        ResolveResult::Unknown(prefix) => match prefix.as_slice() {
+            b"xhtml" => Namespace::Xhtml,
+            b"image" => Namespace::Image,
+            _ => Namespace::Other,
+        },
+    }
+}
+
+/// Adds the hreflang alternate of an `xhtml:link` element to the page.
+fn push_alternate(url: Option<&mut SitemapUrl>, element: &BytesStart) -> Result<()> {
+    let Some(url) = url else {
+        return Ok(());
+    };
+
+    let mut rel = None;
+    let mut hreflang = None;
+    let mut href = None;
+    for attribute in element.attributes() {
+        let attribute = attribute.context("Failed to read xhtml:link attribute")?;
+        let value = attribute
+            .unescape_value()
+            .context("Failed to read xhtml:link attribute value")?
+            .to_string();
+        match attribute.key.local_name().as_ref() {
+            b"rel" => rel = Some(value),
+            b"hreflang" => hreflang = Some(value),
+            b"href" => href = Some(value),
+            _ => (),
+        }
+    }
+
+    if let (Some("alternate"), Some(hreflang), Some(href)) = (rel.as_deref(), hreflang, href) {
+        url.alternates.push((hreflang, href));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    const URLSET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+    xmlns:xhtml="http://www.w3.org/1999/xhtml"
+    xmlns:img="http://www.google.com/schemas/sitemap-image/1.1">
+  <url>
+    <loc>https://addons.prestashop.com/fr/seo/8734-seo-expert.html</loc>
+    <lastmod>2024-05-02T10:00:00+02:00</lastmod>
+    <changefreq>weekly</changefreq>
+    <xhtml:link rel="alternate" hreflang="en" href="https://addons.prestashop.com/en/seo/8734-seo-expert.html"/>
+    <img:image>
+      <img:loc>https://addons.prestashop.com/8734/cover.jpg</img:loc>
+      <img:title>SEO Expert &amp; more</img:title>
+    </img:image>
+  </url>
+</urlset>"#;
+
+    fn parse(body: Vec<u8>) -> Vec<SitemapItem> {
+        read_sitemap(Cursor::new(body)).unwrap()
+    }
+
+    #[test]
+    fn parses_alternates_and_images() {
+        let expected = vec![SitemapItem::Url(SitemapUrl {
+            loc: "https://addons.prestashop.com/fr/seo/8734-seo-expert.html".to_string(),
+            last_mod: Some("2024-05-02T10:00:00+02:00".to_string()),
+            change_freq: Some("weekly".to_string()),
+            alternates: vec![(
+                "en".to_string(),
+                "https://addons.prestashop.com/en/seo/8734-seo-expert.html".to_string(),
+            )],
+            images: vec![SitemapImage {
+                loc: "https://addons.prestashop.com/8734/cover.jpg".to_string(),
+                title: Some("SEO Expert & more".to_string()),
+                caption: None,
+            }],
+        })];
+
+        assert_eq!(parse(URLSET.as_bytes().to_vec()), expected);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(URLSET.as_bytes()).unwrap();
+        assert_eq!(parse(encoder.finish().unwrap()), expected);
+    }
+
+    #[tokio::test]
+    async fn parses_a_gzipped_sitemap_while_it_is_downloaded() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(URLSET.as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+
+        let (sender, receiver) = mpsc::channel(SITEMAP_CHUNKS);
+        let items =
+            task::spawn_blocking(move || read_sitemap(BufReader::new(ChunkReader::new(receiver))));
+        for chunk in body.chunks(7) {
+            sender.send(Ok(chunk.to_vec())).await.unwrap();
+        }
+        drop(sender);
+
+        assert_eq!(
+            items.await.unwrap().unwrap(),
+            parse(URLSET.as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn parses_browser_displayed_sitemap_index() {
+        let chrome = r#"<html><head></head><body><div id="webkit-xml-viewer-source-xml"><sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"><sitemap><loc>https://addons.prestashop.com/sitemap_fr_1.xml</loc><lastmod>2024-05-02</lastmod></sitemap></sitemapindex></div></body></html>"#;
+        let firefox = r#"<html><body><pre>&lt;sitemapindex&gt;&lt;sitemap&gt;&lt;loc&gt;https://addons.prestashop.com/sitemap_fr_1.xml&lt;/loc&gt;&lt;lastmod&gt;2024-05-02&lt;/lastmod&gt;&lt;/sitemap&gt;&lt;/sitemapindex&gt;</pre></body></html>"#;
+        let expected = vec![SitemapItem::Sitemap(SitemapIndexEntry {
+            loc: "https://addons.prestashop.com/sitemap_fr_1.xml".to_string(),
+            last_mod: Some("2024-05-02".to_string()),
+        })];
+
+        assert_eq!(parse(chrome.as_bytes().to_vec()), expected);
+        assert_eq!(parse(firefox.as_bytes().to_vec()), expected);
+    }
+}
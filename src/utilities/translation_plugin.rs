@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+use tokio::sync::Mutex;
+
+use crate::config::get_configuration::get_configuration_value;
+
+/// WordPress plugin translating the products and categories of a multilingual shop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranslationPlugin {
+    /// Only the main language is imported.
+    None,
+    /// WPML with WooCommerce Multilingual, using the `lang` and `translation_of` fields.
+    Wpml,
+    /// Polylang for WooCommerce, using the `lang` and `translations` fields.
+    Polylang,
+}
+
+impl TranslationPlugin {
+    /// Language used to filter the product and category lookups, `None` without plugin.
+    pub fn lookup_lang(self, lang: &str) -> Option<&str> {
+        match self {
+            TranslationPlugin::None => None,
+            TranslationPlugin::Wpml | TranslationPlugin::Polylang => Some(lang),
+        }
+    }
+
+    /// Returns the fields setting the language of a created product or category.
+    ///
+    /// `original` is the main language and the ID of the item in this language,
+    /// `None` when the item is itself in the main language or isn't translated yet.
+    pub fn fields(self, lang: &str, original: Option<(&str, u32)>) -> Map<String, Value> {
+        let mut fields = Map::new();
+        match self {
+            TranslationPlugin::None => {}
+            TranslationPlugin::Wpml => {
+                fields.insert("lang".to_string(), json!(lang));
+                if let Some((_, id)) = original {
+                    fields.insert("translation_of".to_string(), json!(id));
+                }
+            }
+            TranslationPlugin::Polylang => {
+                fields.insert("lang".to_string(), json!(lang));
+                if let Some((main_lang, id)) = original {
+                    fields.insert("translations".to_string(), json!({ main_lang: id }));
+                }
+            }
+        }
+        fields
+    }
+}
+
+/// Loads the translation plugin of the shop from the configuration.
+pub async fn load_translation_plugin(db: &Arc<Mutex<Connection>>) -> Result<TranslationPlugin> {
+    let translation_plugin = get_configuration_value(db, "translation_plugin").await?;
+
+    match translation_plugin.as_str() {
+        "none" | "" => Ok(TranslationPlugin::None),
+        "wpml" => Ok(TranslationPlugin::Wpml),
+        "polylang" => Ok(TranslationPlugin::Polylang),
+        other => Err(anyhow::anyhow!(
+            "Invalid translation_plugin value: {} (expected none, wpml or polylang)",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_translations_to_the_main_language() {
+        assert!(TranslationPlugin::None
+            .fields("en", Some(("fr", 12)))
+            .is_empty());
+        assert_eq!(
+            Value::Object(TranslationPlugin::Wpml.fields("en", Some(("fr", 12)))),
+            json!({"lang": "en", "translation_of": 12})
+        );
+        assert_eq!(
+            Value::Object(TranslationPlugin::Polylang.fields("en", Some(("fr", 12)))),
+            json!({"lang": "en", "translations": {"fr": 12}})
+        );
+        assert_eq!(
+            Value::Object(TranslationPlugin::Polylang.fields("fr", None)),
+            json!({"lang": "fr"})
+        );
+    }
+}
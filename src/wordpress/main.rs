@@ -1,8 +1,10 @@
 use anyhow::Result;
 use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
-use serde_json::Value;
+use reqwest::Response;
+use serde_json::{json, Map, Value};
 
+use crate::extractors::ps_addons::extract_images::ProductImage;
 use crate::wordpress::woocommerce::create_product::ProductCreationResult;
 use crate::wordpress::woocommerce::find_category::CategoryInfo;
 use crate::wordpress::woocommerce::find_product::ProductInfo;
@@ -44,6 +46,41 @@ impl Auth {
     }
 }
 
+/// A global product attribute and the names of its terms assigned to the product.
+#[derive(Debug, Clone)]
+pub struct ProductAttribute {
+    pub id: u32,
+    pub options: Vec<String>,
+}
+
+/// Sale price of a product, with the promotion dates if known.
+#[derive(Debug, Clone)]
+pub struct ProductSale {
+    pub sale_price: String,
+    /// Start of the promotion, `YYYY-MM-DDTHH:MM:SS` in the site timezone.
+    pub date_on_sale_from: Option<String>,
+    /// End of the promotion, `YYYY-MM-DDTHH:MM:SS` in the site timezone.
+    pub date_on_sale_to: Option<String>,
+}
+
+/// Returns the WooCommerce sale fields of a product, clearing the sale if there is none.
+pub fn sale_fields(sale: Option<&ProductSale>) -> Map<String, Value> {
+    let mut fields = Map::new();
+    fields.insert(
+        "sale_price".to_string(),
+        json!(sale.map(|sale| sale.sale_price.as_str()).unwrap_or("")),
+    );
+    fields.insert(
+        "date_on_sale_from".to_string(),
+        json!(sale.and_then(|sale| sale.date_on_sale_from.as_deref())),
+    );
+    fields.insert(
+        "date_on_sale_to".to_string(),
+        json!(sale.and_then(|sale| sale.date_on_sale_to.as_deref())),
+    );
+    fields
+}
+
 pub trait CreateProduct {
     /// Creates a product in WordPress WooCommerce using the provided details.
     ///
@@ -58,14 +95,19 @@ pub trait CreateProduct {
     /// * `description` - A detailed description of the product.
     /// * `regular_price` - The regular price of the product.
     /// * `categories` - A vector of category IDs to which the product belongs.
-    /// * `images` - A vector of image URLs for the product.
+    /// * `images` - The images of the product, the first one is used as cover.
     /// * `ps_product_id` - The PrestaShop product ID.
     /// * `ps_product_url` - The PrestaShop product URL.
+    /// * `meta_data` - Additional meta data as key/value pairs.
+    /// * `attributes` - The global attributes of the product with their terms.
+    /// * `sale` - The sale price and promotion dates, if the product is discounted.
+    /// * `translation` - The language fields of the translation plugin, empty without plugin.
     ///
     /// # Returns
     ///
     /// A `Result` containing the response from the WordPress API as a `Value` on success,
     /// or an error on failure.
+    #[allow(clippy::too_many_arguments)]
     async fn create_product(
         &self,
         name: String,
@@ -77,14 +119,127 @@ pub trait CreateProduct {
         description: String,
         regular_price: String,
         categories: Vec<u32>,
-        images: &Vec<String>,
+        images: &[ProductImage],
         ps_product_id: u32,
         ps_product_url: String,
+        meta_data: &[(String, String)],
+        attributes: &[ProductAttribute],
+        sale: Option<&ProductSale>,
+        translation: &Map<String, Value>,
     ) -> Result<ProductCreationResult>;
 }
 
+pub trait UpdateProduct {
+    /// Updates fields of an existing product in WordPress WooCommerce.
+    ///
+    /// # Arguments
+    ///
+    /// * `product_id` - The WooCommerce product ID.
+    /// * `fields` - The fields to update, e.g. `{"sale_price": "59.99"}`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated product as a `Value` on success,
+    /// or an error on failure.
+    async fn update_product(&self, product_id: u32, fields: Value) -> Result<Value>;
+}
+
+pub trait DeleteProduct {
+    /// Permanently deletes a product in WordPress WooCommerce, bypassing the trash.
+    ///
+    /// # Arguments
+    ///
+    /// * `product_id` - The WooCommerce product ID.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the deleted product as a `Value` on success,
+    /// or an error on failure.
+    async fn delete_product(&self, product_id: u32) -> Result<Value>;
+}
+
+pub trait FindOrCreateAttribute {
+    /// Returns the ID of the global product attribute with the given name,
+    /// creating it if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the attribute, e.g. "PrestaShop".
+    async fn find_or_create_attribute(&self, name: &str) -> Result<u32>;
+
+    /// Returns the ID of the term with the given name of a global product attribute,
+    /// creating it if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `attribute_id` - The ID of the attribute.
+    /// * `name` - The name of the term, e.g. "8".
+    async fn find_or_create_attribute_term(&self, attribute_id: u32, name: &str) -> Result<u32>;
+}
+
+pub trait FindOrCreateBrand {
+    /// Returns the ID of the product brand with the given name, creating it if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the brand, e.g. the developer of the module.
+    async fn find_or_create_brand(&self, name: &str) -> Result<u32>;
+
+    /// Updates fields of a product brand.
+    ///
+    /// # Arguments
+    ///
+    /// * `brand_id` - The ID of the brand.
+    /// * `fields` - The fields to update, e.g. `{"description": "..."}`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated brand as a `Value` on success,
+    /// or an error on failure.
+    async fn update_brand(&self, brand_id: u32, fields: Value) -> Result<Value>;
+}
+
+pub trait CreateProductReview {
+    /// Creates a product review in WordPress WooCommerce.
+    ///
+    /// # Arguments
+    ///
+    /// * `product_id` - The WooCommerce product ID.
+    /// * `reviewer` - The name of the reviewer.
+    /// * `reviewer_email` - The email of the reviewer, required by WooCommerce.
+    /// * `review` - The content of the review.
+    /// * `rating` - The rating, from 0 to 5.
+    /// * `date_created` - The date of the review in ISO 8601 format, if known.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the created review as a `Value` on success,
+    /// or an error on failure.
+    async fn create_product_review(
+        &self,
+        product_id: u32,
+        reviewer: String,
+        reviewer_email: String,
+        review: String,
+        rating: u8,
+        date_created: Option<String>,
+    ) -> Result<Value>;
+}
+
 pub trait FindProductByCustomField {
-    async fn find_product_by_custom_field(&self, name: &str, status: &str) -> Result<ProductInfo>;
+    /// Searches a product by the value of a meta field, e.g. `ps_product_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The key of the meta field.
+    /// * `status` - The value of the meta field.
+    /// * `lang` - The language of the product, when the shop has a translation plugin.
+    async fn find_product_by_custom_field(
+        &self,
+        name: &str,
+        status: &str,
+        lang: Option<&str>,
+    ) -> Result<ProductInfo>;
 }
 
 // pub trait CreatePage {
@@ -105,6 +260,7 @@ pub trait FindCategoryByCustomField {
     /// # Arguments
     ///
     /// * `custom_field` - The PrestaShop addons category ID to search for.
+    /// * `lang` - The language of the category, when the shop has a translation plugin.
     ///
     /// # Returns
     ///
@@ -118,7 +274,11 @@ pub trait FindCategoryByCustomField {
     /// - Constructing the HTTP headers.
     /// - Sending the HTTP request.
     /// - Parsing the response body as JSON.
-    async fn find_category_by_custom_field(&self, custom_field: u32) -> Result<CategoryInfo>;
+    async fn find_category_by_custom_field(
+        &self,
+        custom_field: u32,
+        lang: Option<&str>,
+    ) -> Result<CategoryInfo>;
 }
 
 pub trait CreateCategory {
@@ -133,6 +293,7 @@ pub trait CreateCategory {
     /// * `name` - The name of the category to be created.
     /// * `parent` - The ID of the parent category.
     /// * `ps_addons_cat_id` - The ID of the PrestaShop category.
+    /// * `translation` - The language fields of the translation plugin, empty without plugin.
     ///
     /// # Returns
     ///
@@ -152,9 +313,20 @@ pub trait CreateCategory {
         name: String,
         parent: u32,
         ps_addons_cat_id: u32,
+        translation: &Map<String, Value>,
     ) -> Result<Value>;
 }
 
-// pub trait UploadImage {
-//     async fn upload_image(&self, image_url: &str) -> Result<Response>;
-// }
+pub trait UploadImage {
+    /// Downloads an image and uploads it to the WordPress media library.
+    ///
+    /// # Arguments
+    ///
+    /// * `image_url` - The URL of the image to upload.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the response of the WordPress media API on success,
+    /// or an error on failure.
+    async fn upload_image(&self, image_url: &str) -> Result<Response>;
+}
@@ -30,7 +30,7 @@ impl UploadImage for Auth {
 
         // Build the URL for the media API
         let url = format!("{}/wp-json/wp/v2/media", self.base_url);
-        let file_name = image_url.split('/').last().unwrap_or("default.jpg");
+        let file_name = image_url.split('/').next_back().unwrap_or("default.jpg");
 
         let part = Part::stream(image_bytes)
             .file_name(file_name.to_string())
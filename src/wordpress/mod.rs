@@ -1,2 +1,3 @@
 pub mod main;
+mod media;
 mod woocommerce;
@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use reqwest::{Client, StatusCode};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 
 use crate::wordpress::main::{Auth, CreateCategory};
 
@@ -10,17 +10,21 @@ impl CreateCategory for Auth {
         name: String,
         parent: u32,
         ps_addons_cat_id: u32,
+        translation: &Map<String, Value>,
     ) -> Result<Value> {
         let client = Client::new();
         let headers = self.create_headers(None)?;
 
         let create_url = format!("{}/wp-json/wc/v3/products/categories", self.base_url);
 
-        let category = json!({
+        let mut category = json!({
             "name": name,
             "parent": parent,
             "ps_addons_cat_id": ps_addons_cat_id,
         });
+        if let Some(category) = category.as_object_mut() {
+            category.extend(translation.clone());
+        }
 
         let response = client
             .post(&create_url)
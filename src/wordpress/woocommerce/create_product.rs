@@ -1,13 +1,12 @@
 use anyhow::{Context, Result};
 use reqwest::Client;
-use serde_json::{from_str, json, Value};
+use serde_json::{from_str, json, Map, Value};
 
-use crate::wordpress::main::{Auth, CreateProduct};
+use crate::extractors::ps_addons::extract_images::ProductImage;
+use crate::wordpress::main::{sale_fields, Auth, CreateProduct, ProductAttribute, ProductSale};
 
 #[derive(Debug)]
 pub struct ProductCreationResult {
-    pub http_status: u16,
-    pub response_body: String,
     pub response_json: Value,
 }
 
@@ -23,16 +22,20 @@ impl CreateProduct for Auth {
         description: String,
         regular_price: String,
         categories: Vec<u32>,
-        images: &Vec<String>,
+        images: &[ProductImage],
         ps_product_id: u32,
         ps_product_url: String,
+        meta_data: &[(String, String)],
+        attributes: &[ProductAttribute],
+        sale: Option<&ProductSale>,
+        translation: &Map<String, Value>,
     ) -> Result<ProductCreationResult> {
         let client = Client::new();
         let headers = self.create_headers(None)?;
 
         let create_url = format!("{}/wp-json/wc/v3/products", self.base_url);
 
-        let product = json!({
+        let mut product = json!({
             "name": name,
             "type": r#type,
             "status": status,
@@ -42,7 +45,13 @@ impl CreateProduct for Auth {
             "description": description,
             "regular_price": regular_price,
             "categories": categories.iter().map(|&id| json!({ "id": id })).collect::<Vec<_>>(),
-            "images": images.iter().map(|url| json!({ "src": url })).collect::<Vec<_>>(),
+            "images": images.iter().map(|image| json!({ "src": image.url, "alt": image.alt })).collect::<Vec<_>>(),
+            "attributes": attributes.iter().map(|attribute| json!({
+                "id": attribute.id,
+                "visible": true,
+                "variation": false,
+                "options": attribute.options,
+            })).collect::<Vec<_>>(),
             "meta_data": [
             {
               "key": "ps_product_id",
@@ -55,6 +64,20 @@ impl CreateProduct for Auth {
           ]
         });
 
+        if let Some(product_meta_data) = product["meta_data"].as_array_mut() {
+            for (key, value) in meta_data {
+                product_meta_data.push(json!({ "key": key, "value": value }));
+            }
+        }
+
+        if let (Some(product), Some(sale)) = (product.as_object_mut(), sale) {
+            product.extend(sale_fields(Some(sale)));
+        }
+
+        if let Some(product) = product.as_object_mut() {
+            product.extend(translation.clone());
+        }
+
         let response = client
             .post(&create_url)
             .headers(headers)
@@ -73,8 +96,6 @@ impl CreateProduct for Auth {
         let body_json: Result<Value, _> = from_str(&response_body);
 
         let result = ProductCreationResult {
-            http_status: status_code,
-            response_body: response_body.clone(),
             response_json: body_json.unwrap_or(json!({"raw_body": response_body})),
         };
 
@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+
+use crate::wordpress::main::{Auth, CreateProductReview};
+
+impl CreateProductReview for Auth {
+    async fn create_product_review(
+        &self,
+        product_id: u32,
+        reviewer: String,
+        reviewer_email: String,
+        review: String,
+        rating: u8,
+        date_created: Option<String>,
+    ) -> Result<Value> {
+        let client = Client::new();
+        let headers = self.create_headers(None)?;
+
+        let create_url = format!("{}/wp-json/wc/v3/products/reviews", self.base_url);
+
+        let mut product_review = json!({
+            "product_id": product_id,
+            "reviewer": reviewer,
+            "reviewer_email": reviewer_email,
+            "review": review,
+            "rating": rating,
+            "status": "approved",
+        });
+
+        if let Some(date_created) = date_created {
+            product_review["date_created"] = json!(date_created);
+        }
+
+        let response = client
+            .post(&create_url)
+            .headers(headers)
+            .json(&product_review)
+            .send()
+            .await
+            .context("Failed to send create product review request")?;
+
+        let status_code = response.status();
+        let response_body = response
+            .text()
+            .await
+            .context("Failed to read response body")?;
+
+        match status_code {
+            StatusCode::OK | StatusCode::CREATED => serde_json::from_str(&response_body)
+                .context("Failed to parse response body as JSON"),
+            _ => Err(anyhow::anyhow!(
+                "HTTP {}: {}",
+                status_code.as_u16(),
+                response_body
+            )),
+        }
+    }
+}
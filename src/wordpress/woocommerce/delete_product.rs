@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+use crate::wordpress::main::{Auth, DeleteProduct};
+
+impl DeleteProduct for Auth {
+    async fn delete_product(&self, product_id: u32) -> Result<Value> {
+        let client = Client::new();
+        let headers = self.create_headers(None)?;
+
+        let delete_url = format!(
+            "{}/wp-json/wc/v3/products/{}?force=true",
+            self.base_url, product_id
+        );
+
+        let response = client
+            .delete(&delete_url)
+            .headers(headers)
+            .send()
+            .await
+            .context("Failed to send delete product request")?;
+
+        let status_code = response.status();
+        let response_body = response
+            .text()
+            .await
+            .context("Failed to read response body")?;
+
+        match status_code {
+            StatusCode::OK => serde_json::from_str(&response_body)
+                .context("Failed to parse response body as JSON"),
+            _ => Err(anyhow::anyhow!(
+                "HTTP {}: {}",
+                status_code.as_u16(),
+                response_body
+            )),
+        }
+    }
+}
@@ -8,20 +8,26 @@ pub struct CategoryInfo {
     pub status: String,
     pub message: String,
     pub category_id: Option<u32>,
-    pub ps_addons_cat_id: Option<u32>,
     pub category_name: Option<String>,
 }
 
 impl FindCategoryByCustomField for Auth {
-    async fn find_category_by_custom_field(&self, custom_field: u32) -> Result<CategoryInfo> {
+    async fn find_category_by_custom_field(
+        &self,
+        custom_field: u32,
+        lang: Option<&str>,
+    ) -> Result<CategoryInfo> {
         let client = Client::new();
         let headers = self.create_headers(None)?;
 
-        let api_url = format!(
+        let mut api_url = format!(
             "{}/wp-json/wc/v3/products/categories?ps_addons_cat_id={}",
             self.base_url(),
             custom_field
         );
+        if let Some(lang) = lang {
+            api_url.push_str(&format!("&lang={}", lang));
+        }
 
         let response = client
             .get(api_url)
@@ -43,7 +49,6 @@ impl FindCategoryByCustomField for Auth {
                             status: "found".to_string(),
                             message: "Category already exists".to_string(),
                             category_id: category["id"].as_u64().map(|id| id as u32),
-                            ps_addons_cat_id: Some(custom_field),
                             category_name: category["name"].as_str().map(|name| name.to_string()),
                         });
                     }
@@ -53,7 +58,6 @@ impl FindCategoryByCustomField for Auth {
                     status: "notfound".to_string(),
                     message: "No category found with the given ID".to_string(),
                     category_id: None,
-                    ps_addons_cat_id: None,
                     category_name: None,
                 });
             }
@@ -65,7 +69,6 @@ impl FindCategoryByCustomField for Auth {
             status: "error".to_string(),
             message: "Unknown error occurred".to_string(),
             category_id: None,
-            ps_addons_cat_id: None,
             category_name: None,
         })
     }
@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+
+use crate::wordpress::main::{Auth, FindOrCreateAttribute};
+
+impl FindOrCreateAttribute for Auth {
+    async fn find_or_create_attribute(&self, name: &str) -> Result<u32> {
+        let client = Client::new();
+        let attributes_url = format!("{}/wp-json/wc/v3/products/attributes", self.base_url);
+
+        let response = client
+            .get(&attributes_url)
+            .headers(self.create_headers(None)?)
+            .send()
+            .await
+            .context("Failed to send list attributes request")?;
+        let attributes: Vec<Value> = parse_response(response).await?;
+
+        if let Some(id) = attributes
+            .iter()
+            .find(|attribute| {
+                attribute["name"]
+                    .as_str()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+            .and_then(|attribute| attribute["id"].as_u64())
+        {
+            return Ok(id as u32);
+        }
+
+        let response = client
+            .post(&attributes_url)
+            .headers(self.create_headers(None)?)
+            .json(&json!({ "name": name, "type": "select" }))
+            .send()
+            .await
+            .context("Failed to send create attribute request")?;
+        let attribute: Value = parse_response(response).await?;
+
+        attribute["id"]
+            .as_u64()
+            .map(|id| id as u32)
+            .context("Missing ID in create attribute response")
+    }
+
+    async fn find_or_create_attribute_term(&self, attribute_id: u32, name: &str) -> Result<u32> {
+        let client = Client::new();
+        let terms_url = format!(
+            "{}/wp-json/wc/v3/products/attributes/{}/terms",
+            self.base_url, attribute_id
+        );
+
+        let response = client
+            .get(&terms_url)
+            .headers(self.create_headers(None)?)
+            .query(&[("search", name), ("per_page", "100")])
+            .send()
+            .await
+            .context("Failed to send list attribute terms request")?;
+        let terms: Vec<Value> = parse_response(response).await?;
+
+        // The search also matches partially, e.g. "8" matches "1.8"
+        if let Some(id) = terms
+            .iter()
+            .find(|term| term["name"] == name)
+            .and_then(|term| term["id"].as_u64())
+        {
+            return Ok(id as u32);
+        }
+
+        let response = client
+            .post(&terms_url)
+            .headers(self.create_headers(None)?)
+            .json(&json!({ "name": name }))
+            .send()
+            .await
+            .context("Failed to send create attribute term request")?;
+        let term: Value = parse_response(response).await?;
+
+        term["id"]
+            .as_u64()
+            .map(|id| id as u32)
+            .context("Missing ID in create attribute term response")
+    }
+}
+
+async fn parse_response<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let status_code = response.status();
+    let response_body = response
+        .text()
+        .await
+        .context("Failed to read response body")?;
+
+    match status_code {
+        StatusCode::OK | StatusCode::CREATED => {
+            serde_json::from_str(&response_body).context("Failed to parse response body as JSON")
+        }
+        _ => Err(anyhow::anyhow!(
+            "HTTP {}: {}",
+            status_code.as_u16(),
+            response_body
+        )),
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+
+use crate::wordpress::main::{Auth, FindOrCreateBrand};
+
+impl FindOrCreateBrand for Auth {
+    async fn find_or_create_brand(&self, name: &str) -> Result<u32> {
+        let client = Client::new();
+        let brands_url = format!("{}/wp-json/wc/v3/products/brands", self.base_url);
+
+        let response = client
+            .get(&brands_url)
+            .headers(self.create_headers(None)?)
+            .query(&[("search", name), ("per_page", "100")])
+            .send()
+            .await
+            .context("Failed to send list brands request")?;
+        let brands: Vec<Value> = parse_response(response).await?;
+
+        // The search also matches partially, e.g. "Soft" matches "ETS-Soft"
+        if let Some(id) = brands
+            .iter()
+            .find(|brand| {
+                brand["name"]
+                    .as_str()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+            .and_then(|brand| brand["id"].as_u64())
+        {
+            return Ok(id as u32);
+        }
+
+        let response = client
+            .post(&brands_url)
+            .headers(self.create_headers(None)?)
+            .json(&json!({ "name": name }))
+            .send()
+            .await
+            .context("Failed to send create brand request")?;
+        let brand: Value = parse_response(response).await?;
+
+        brand["id"]
+            .as_u64()
+            .map(|id| id as u32)
+            .context("Missing ID in create brand response")
+    }
+
+    async fn update_brand(&self, brand_id: u32, fields: Value) -> Result<Value> {
+        let client = Client::new();
+        let brand_url = format!(
+            "{}/wp-json/wc/v3/products/brands/{}",
+            self.base_url, brand_id
+        );
+
+        let response = client
+            .put(&brand_url)
+            .headers(self.create_headers(None)?)
+            .json(&fields)
+            .send()
+            .await
+            .context("Failed to send update brand request")?;
+
+        parse_response(response).await
+    }
+}
+
+async fn parse_response<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let status_code = response.status();
+    let response_body = response
+        .text()
+        .await
+        .context("Failed to read response body")?;
+
+    match status_code {
+        StatusCode::OK | StatusCode::CREATED => {
+            serde_json::from_str(&response_body).context("Failed to parse response body as JSON")
+        }
+        _ => Err(anyhow::anyhow!(
+            "HTTP {}: {}",
+            status_code.as_u16(),
+            response_body
+        )),
+    }
+}
@@ -6,9 +6,7 @@ use crate::wordpress::main::{Auth, FindProductByCustomField};
 
 pub struct ProductInfo {
     pub status: String,
-    pub message: String,
     pub product_id: Option<u32>,
-    pub custom_field_value: Option<String>,
 }
 
 impl FindProductByCustomField for Auth {
@@ -16,16 +14,20 @@ impl FindProductByCustomField for Auth {
         &self,
         custom_field_key: &str,
         custom_field_value: &str,
+        lang: Option<&str>,
     ) -> Result<ProductInfo> {
         let client = Client::new();
         let headers = self.create_headers(None)?;
 
-        let api_url = format!(
+        let mut api_url = format!(
             "{}/wp-json/wc/v3/products?{}={}",
             self.base_url(),
             custom_field_key,
             custom_field_value
         );
+        if let Some(lang) = lang {
+            api_url.push_str(&format!("&lang={}", lang));
+        }
 
         let response = client
             .get(&api_url)
@@ -46,9 +48,7 @@ impl FindProductByCustomField for Auth {
                         if meta["key"] == custom_field_key && meta["value"] == custom_field_value {
                             return Ok(ProductInfo {
                                 status: "found".to_string(),
-                                message: "Product already exists".to_string(),
                                 product_id: product["id"].as_u64().map(|id| id as u32),
-                                custom_field_value: Some(custom_field_value.to_string()),
                             });
                         }
                     }
@@ -57,9 +57,7 @@ impl FindProductByCustomField for Auth {
 
             Ok(ProductInfo {
                 status: "notfound".to_string(),
-                message: "No product found with the given custom field value".to_string(),
                 product_id: None,
-                custom_field_value: None,
             })
         } else {
             let error_msg = format!("Failed to search for product: {}", response.status());
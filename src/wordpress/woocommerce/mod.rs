@@ -1,4 +1,9 @@
 pub mod create_category;
 pub mod create_product;
+pub mod create_product_review;
+pub mod delete_product;
 pub mod find_category;
+pub mod find_or_create_brand;
+pub mod find_or_create_attribute;
 pub mod find_product;
+pub mod update_product;
@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+use crate::wordpress::main::{Auth, UpdateProduct};
+
+impl UpdateProduct for Auth {
+    async fn update_product(&self, product_id: u32, fields: Value) -> Result<Value> {
+        let client = Client::new();
+        let headers = self.create_headers(None)?;
+
+        let update_url = format!("{}/wp-json/wc/v3/products/{}", self.base_url, product_id);
+
+        let response = client
+            .put(&update_url)
+            .headers(headers)
+            .json(&fields)
+            .send()
+            .await
+            .context("Failed to send update product request")?;
+
+        let status_code = response.status();
+        let response_body = response
+            .text()
+            .await
+            .context("Failed to read response body")?;
+
+        match status_code {
+            StatusCode::OK => serde_json::from_str(&response_body)
+                .context("Failed to parse response body as JSON"),
+            _ => Err(anyhow::anyhow!(
+                "HTTP {}: {}",
+                status_code.as_u16(),
+                response_body
+            )),
+        }
+    }
+}